@@ -0,0 +1,90 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::agent::AgentStream;
+use crate::config;
+use crate::error::{AppError, Result};
+use crate::index;
+use crate::slm::SlmBackend;
+use crate::slm::SlmRequest;
+use log::{debug, trace, warn};
+
+/// Cache-augmented generation: the whole corpus is loaded once, when agent is created, and sent
+/// as the same request prefix with every question, tagged with a cache id, so that backends
+/// keep the evaluated prefix in their KV cache instead of retrieving per question.
+pub struct CagAgent {
+    corpus: String,
+    system: String,
+    documents: String,
+    cache_id: String,
+    backend: Rc<dyn SlmBackend>,
+}
+
+impl CagAgent {
+    const SYSTEM: &'static str = "Question answering agent that answers only from the provided \
+    documents and formats answers in Markdown, naming the document used. If requested information \
+    is missing from documents, respond with 'I do not know'.";
+
+    pub fn new(backend: Rc<dyn SlmBackend>, corpus: &str) -> Result<Self> {
+        trace!("CagAgent::new(backend: Rc<dyn SlmBackend>, corpus: &str) -> Result<Self>");
+        let config = config::get_config();
+        let system = match &config.cag_system {
+            Some(system) => system,
+            None => Self::SYSTEM,
+        }
+        .to_string();
+
+        let paths = config
+            .corpora
+            .get(corpus)
+            .ok_or_else(|| AppError::InvalidConfig(format!("unknown corpus {corpus}")))?;
+        let mut files = Vec::new();
+        for path in paths {
+            index::collect_files(Path::new(path), &mut files)?;
+        }
+
+        let mut documents = String::new();
+        for file in files {
+            let Ok(text) = fs::read_to_string(&file) else {
+                warn!("skip not UTF-8 file {}", file.display());
+                continue;
+            };
+            documents.push_str(&format!(
+                "--- {} ---\n{}\n\n",
+                file.display(),
+                text.trim_end()
+            ));
+        }
+        let mut hasher = DefaultHasher::new();
+        documents.hash(&mut hasher);
+        let cache_id = format!("{corpus}-{:016x}", hasher.finish());
+        debug!(
+            "corpus {corpus}: {} bytes, cache id {cache_id}",
+            documents.len()
+        );
+
+        Ok(Self {
+            corpus: corpus.to_string(),
+            system,
+            documents,
+            cache_id,
+            backend,
+        })
+    }
+
+    pub fn corpus(&self) -> &str {
+        &self.corpus
+    }
+
+    pub async fn exec(&self, mut request: SlmRequest) -> AgentStream {
+        trace!("CagAgent::exec(&self, mut request: SlmRequest) -> AgentStream");
+        if request.system().is_none() {
+            request.set_system(&self.system);
+        }
+        request.set_documents(&self.documents, &self.cache_id);
+        self.backend.stream(request).await
+    }
+}
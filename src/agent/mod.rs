@@ -2,6 +2,7 @@ use crate::error::Result;
 use futures::Stream;
 use std::pin::Pin;
 
+pub mod cag;
 pub mod prompt;
 pub mod rag;
 
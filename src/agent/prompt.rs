@@ -1,29 +1,83 @@
+use std::rc::Rc;
+
 use crate::agent::AgentStream;
 use crate::config;
-use crate::slm::SlmClient;
+use crate::slm::SlmBackend;
 use crate::slm::SlmRequest;
+use crate::tool::ToolRegistry;
+use futures::stream;
 use log::trace;
 
-pub struct PromptAgent{
+/// Plain question answering. If backend supports function calling, tools are advertised and
+/// the final answer, obtained after executing requested calls, is returned as a single chunk.
+pub struct PromptAgent {
     system: String,
+    max_tool_iterations: usize,
+    tools: ToolRegistry,
+    backend: Rc<dyn SlmBackend>,
 }
 
 impl PromptAgent {
     const SYSTEM: &'static str = "Question answering agent";
 
-    pub fn new() -> Self {
+    pub fn new(backend: Rc<dyn SlmBackend>) -> Self {
         let config = config::get_config();
         let system = match &config.prompt_system {
             Some(system) => system,
             None => Self::SYSTEM,
-        }.to_string();
-        Self {system}
+        }
+        .to_string();
+        let max_tool_iterations = config
+            .max_tool_iterations
+            .unwrap_or(ToolRegistry::MAX_ITERATIONS);
+        Self {
+            system,
+            max_tool_iterations,
+            tools: ToolRegistry::new(),
+            backend,
+        }
     }
 
     pub async fn exec(&self, mut request: SlmRequest) -> AgentStream {
         trace!("PromptAgent::exec(&self, mut request: SlmRequest) -> AgentStream");
-        request.set_system(&self.system);
-        let slm = SlmClient::new();
-        slm.exec(request).await
+        if request.system().is_none() {
+            request.set_system(&self.system);
+        }
+        if self.backend.supports_tools() {
+            let answer = self
+                .tools
+                .run(self.backend.as_ref(), request, self.max_tool_iterations)
+                .await;
+            return Box::pin(stream::iter([answer]));
+        }
+        self.backend.stream(request).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use futures::StreamExt;
+
+    use crate::{
+        agent::prompt::PromptAgent,
+        config,
+        slm::{SlmRequest, fake::FakeBackend},
+    };
+
+    #[tokio::test]
+    async fn exec() {
+        config::init_test_config();
+        let backend = Rc::new(FakeBackend::new(&["Hello", " world"]));
+        let agent = PromptAgent::new(backend.clone());
+
+        let stream = agent.exec(SlmRequest::new("greet me")).await;
+        let chunks: Vec<String> = stream.map(|chunk| chunk.unwrap()).collect().await;
+        assert_eq!(chunks.concat(), "Hello world");
+        assert_eq!(
+            backend.systems(),
+            vec![Some("Test question answering agent".to_string())]
+        );
     }
 }
@@ -1,31 +1,77 @@
+use std::rc::Rc;
+
 use crate::agent::AgentStream;
 use crate::config;
-use crate::slm::SlmClient;
+use crate::error::{AppError, Result};
+use crate::index::Index;
+use crate::slm::SlmBackend;
 use crate::slm::SlmRequest;
-use log::trace;
+use futures::stream;
+use log::{debug, trace};
 
-pub struct RagAgent{
+/// Question answering over a named context. If a local index exists with context name, top
+/// matching chunks are retrieved client-side and sent as context, with source citations;
+/// otherwise context name is forwarded for the SLM service to resolve.
+pub struct RagAgent {
     system: String,
+    top_k: usize,
+    backend: Rc<dyn SlmBackend>,
 }
 
 impl RagAgent {
     const SYSTEM: &'static str = "Contextual question answering agent that always uses only \
     the provided context and formats answers in Markdown. If requested information is missing \
     from context, respond with 'I do not know'.";
+    const TOP_K: usize = 5;
 
-    pub fn new() -> Self {
+    pub fn new(backend: Rc<dyn SlmBackend>) -> Self {
         let config = config::get_config();
         let system = match &config.rag_system {
             Some(system) => system,
             None => Self::SYSTEM,
-        }.to_string();
-        Self {system}
+        }
+        .to_string();
+        let top_k = config.rag_top_k.unwrap_or(Self::TOP_K);
+        Self {
+            system,
+            top_k,
+            backend,
+        }
     }
 
     pub async fn exec(&self, mut request: SlmRequest) -> AgentStream {
         trace!("RagAgent::exec(&self, mut request: SlmRequest) -> AgentStream");
-        request.set_system(&self.system);
-        let slm = SlmClient::new();
-        slm.exec(request).await
+        if request.system().is_none() {
+            request.set_system(&self.system);
+        }
+        if let Some(name) = request.context().map(str::to_string)
+            && Index::exists(&name)
+        {
+            match self.retrieve(&name, request.prompt()).await {
+                Ok(context) => request.set_context(&context),
+                Err(e) => return Box::pin(stream::iter([Err(e)])),
+            }
+        }
+        self.backend.stream(request).await
+    }
+
+    async fn retrieve(&self, name: &str, prompt: &str) -> Result<String> {
+        trace!("RagAgent::retrieve(&self, name: &str, prompt: &str) -> Result<String>");
+        let index = Index::load(name)?;
+        let vectors = self.backend.embed(&[prompt.to_string()]).await?;
+        let vector = vectors
+            .first()
+            .ok_or_else(|| AppError::Fatal("missing prompt embedding".to_string()))?;
+
+        let chunks = index.search(vector, self.top_k)?;
+        debug!("retrieved {} chunks from index {name}", chunks.len());
+        let mut context = String::from("Sources, cite them by bracketed number:");
+        for (number, chunk) in (1..).zip(chunks) {
+            context.push_str(&format!(
+                "\n\n[{number}] {}:{}\n{}",
+                chunk.source, chunk.line, chunk.text
+            ));
+        }
+        Ok(context)
     }
 }
@@ -0,0 +1,127 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Read},
+    path::Path,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use log::{debug, trace};
+
+use crate::error::{AppError, Result};
+
+/// Text content attached to the prompt, e.g. a file or data piped on standard input.
+pub struct Attachment {
+    name: String,
+    content: String,
+}
+
+impl Attachment {
+    /// Default size limit, in bytes, used when not configured.
+    pub const LIMIT: usize = 100 * 1024;
+    const STDIN: &'static str = "stdin";
+    /// Number of leading bytes inspected for NUL characters when detecting binary content.
+    const PROBE_SIZE: usize = 8 * 1024;
+    /// Time to wait for the first bytes of piped input; an open but idle standard input, e.g.
+    /// under cron or some IDE runners, is ignored instead of blocking.
+    const STDIN_WAIT: Duration = Duration::from_secs(3);
+    const CHUNK_SIZE: usize = 8 * 1024;
+
+    pub fn from_file(path: &str, limit: usize) -> Result<Self> {
+        trace!("Attachment::from_file(path: &str, limit: usize) -> Result<Self>");
+        let size = fs::metadata(path)?.len();
+        if size > limit as u64 {
+            return Err(AppError::Attachment(format!(
+                "{path} has {size} bytes, exceeding limit of {limit}"
+            )));
+        }
+        let name = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(path.to_string());
+        Self::from_bytes(&name, fs::read(path)?, limit)
+    }
+
+    /// Read standard input if it is piped; returns none when attached to a terminal, empty, or
+    /// without data for [Self::STDIN_WAIT]. Once data arrives, input is read to its end.
+    pub fn from_stdin(limit: usize) -> Result<Option<Self>> {
+        trace!("Attachment::from_stdin(limit: usize) -> Result<Option<Self>>");
+        if io::stdin().is_terminal() {
+            return Ok(None);
+        }
+        // blocking reads run on a thread left behind if no data arrives in time
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut stdin = io::stdin().lock().take(limit as u64 + 1);
+            let mut chunk = vec![0u8; Self::CHUNK_SIZE];
+            loop {
+                let read = stdin.read(&mut chunk).map(|count| chunk[..count].to_vec());
+                let end = !matches!(&read, Ok(bytes) if !bytes.is_empty());
+                if sender.send(read).is_err() || end {
+                    return;
+                }
+            }
+        });
+        let Ok(first) = receiver.recv_timeout(Self::STDIN_WAIT) else {
+            debug!("no data on stdin, ignored");
+            return Ok(None);
+        };
+
+        let mut bytes = Vec::new();
+        for chunk in std::iter::once(first).chain(receiver.iter()) {
+            let chunk = chunk?;
+            if chunk.is_empty() {
+                break;
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        Self::from_bytes(Self::STDIN, bytes, limit).map(Some)
+    }
+
+    fn from_bytes(name: &str, bytes: Vec<u8>, limit: usize) -> Result<Self> {
+        if bytes.len() > limit {
+            return Err(AppError::Attachment(format!(
+                "{name} exceeds limit of {limit} bytes"
+            )));
+        }
+        let probe = &bytes[..bytes.len().min(Self::PROBE_SIZE)];
+        if probe.contains(&0) {
+            return Err(AppError::Attachment(format!("{name} is binary")));
+        }
+        let content = String::from_utf8(bytes)
+            .map_err(|_| AppError::Attachment(format!("{name} is not UTF-8 text")))?;
+        debug!("attachment {name}: {} bytes", content.len());
+        Ok(Self {
+            name: name.to_string(),
+            content,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::attachment::Attachment;
+
+    #[test]
+    fn from_bytes() {
+        let attachment = Attachment::from_bytes("notes.txt", b"line".to_vec(), 10).unwrap();
+        assert_eq!(attachment.name(), "notes.txt");
+        assert_eq!(attachment.content(), "line");
+
+        assert!(Attachment::from_bytes("big.txt", vec![b'x'; 11], 10).is_err());
+        assert!(Attachment::from_bytes("image.png", vec![0x89, b'P', 0, 0], 10).is_err());
+        assert!(Attachment::from_bytes("latin1.txt", vec![0xE9, b'a'], 10).is_err());
+    }
+}
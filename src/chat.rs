@@ -0,0 +1,254 @@
+use std::{fs, rc::Rc};
+
+use futures_util::StreamExt;
+use log::{debug, trace};
+use rustyline::{DefaultEditor, error::ReadlineError};
+use tokio::io::{self, AsyncWriteExt};
+
+use crate::{
+    agent::{cag::CagAgent, prompt::PromptAgent, rag::RagAgent},
+    attachment::Attachment,
+    config,
+    error::Result,
+    router::{Route, Router},
+    session::{self, Session},
+    slm::{Message, Role, SlmBackend, SlmRequest},
+};
+
+/// Interactive multi-turn conversation; history is kept locally and sent with every request.
+/// If a session is attached, history is resumed from and persisted to it. If backend is hosted
+/// by a configured server, prompts wait for the server to be ready.
+pub struct Chat {
+    backend: Rc<dyn SlmBackend>,
+    router: Router,
+    system: Option<String>,
+    context: Option<String>,
+    cag: Option<CagAgent>,
+    history: Vec<Message>,
+    session: Option<Session>,
+}
+
+impl Chat {
+    const PROMPT: &'static str = "> ";
+    const TRANSCRIPT: &'static str = "chat.md";
+    const HELP: &'static str = "\
+/clear             clear conversation history
+/system [role]     set SLM system role, reset to agent default if missing
+/context [name]    set RAG context name, use plain prompt agent if missing
+/save [path]       save conversation as Markdown, default to chat.md
+/exit              leave chat";
+
+    pub fn new(
+        backend: Rc<dyn SlmBackend>,
+        router: Router,
+        system: Option<String>,
+        context: Option<String>,
+        cag: Option<CagAgent>,
+        session: Option<Session>,
+    ) -> Self {
+        let history = match &session {
+            Some(session) => session.history().to_vec(),
+            None => Vec::new(),
+        };
+        Self {
+            backend,
+            router,
+            system,
+            context,
+            cag,
+            history,
+            session,
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        trace!("Chat::run(&mut self) -> Result<()>");
+        let mut editor = DefaultEditor::new()?;
+        loop {
+            let line = match editor.readline(Self::PROMPT) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let _ = editor.add_history_entry(line);
+
+            let result = match line.strip_prefix('/') {
+                Some("exit" | "quit") => break,
+                Some(slash_command) => self.slash_command(slash_command),
+                None => self.exchange(line, &[]).await,
+            };
+            if let Err(e) = result {
+                eprintln!("{e}");
+            }
+        }
+        Ok(())
+    }
+
+    fn slash_command(&mut self, line: &str) -> Result<()> {
+        trace!("Chat::slash_command(&mut self, line: &str) -> Result<()>");
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+        let argument = (!argument.is_empty()).then(|| argument.to_string());
+        debug!("slash command: {name}, argument: {argument:?}");
+
+        match name {
+            "clear" => {
+                self.history.clear();
+                if let Some(session) = &mut self.session {
+                    session.clear()?;
+                }
+            }
+            "system" => self.system = argument,
+            "context" => self.context = argument,
+            "save" => {
+                let path = argument.as_deref().unwrap_or(Self::TRANSCRIPT);
+                fs::write(path, session::markdown(&self.history))?;
+                println!("Conversation saved to {path}");
+            }
+            "help" => println!("{}", Self::HELP),
+            _ => println!("Unknown command /{name}\n{}", Self::HELP),
+        }
+        Ok(())
+    }
+
+    /// Route prompt and print the response; SLM replies are recorded in history.
+    pub async fn exchange(&mut self, prompt: &str, attachments: &[Attachment]) -> Result<()> {
+        trace!("Chat::exchange(&mut self, prompt: &str, attachments: &[Attachment]) -> Result<()>");
+        let config = config::get_config();
+        let mut request = SlmRequest::new(prompt);
+        if let Some(system) = &self.system {
+            request.set_system(system);
+        }
+        if let Some(profile) = &config.user_profile {
+            request.set_profile(profile);
+        }
+        if let Some(settings) = &config.system_settings {
+            request.set_settings(settings);
+        }
+        request.set_history(&self.history);
+
+        let cag = self.cag.as_ref().map(CagAgent::corpus);
+        let decision = self
+            .router
+            .route(prompt, self.context.as_deref(), cag)
+            .await;
+        let backend = self.backend.clone();
+        if let Route::Command(command) = &decision.route
+            && let Some(response) = command.exec(&backend).await?
+        {
+            println!("{response}");
+            return Ok(());
+        }
+        let route = Self::prepare(&mut request, decision.route, attachments);
+        let mut stream = match route {
+            Route::Command(_) | Route::Prompt | Route::Template { .. } => {
+                PromptAgent::new(backend).exec(request).await
+            }
+            Route::Rag(context) => {
+                request.set_context(&context);
+                RagAgent::new(backend).exec(request).await
+            }
+            Route::Cag(corpus) => self.cag_agent(&corpus)?.exec(request).await,
+        };
+
+        let mut reply = String::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            print!("{chunk}");
+            let _ = io::stdout().flush().await;
+            reply.push_str(&chunk);
+        }
+        println!();
+
+        let exchange = [
+            Message::new(Role::User, prompt),
+            Message::new(Role::Assistant, &reply),
+        ];
+        if let Some(session) = &mut self.session {
+            session.append(&exchange)?;
+        }
+        self.history.extend(exchange);
+        Ok(())
+    }
+
+    /// Apply prompt template of a user command route, if any, then append attachments so that
+    /// they are sent whatever prompt the route ends up with. Returns the route to answer with.
+    fn prepare(request: &mut SlmRequest, route: Route, attachments: &[Attachment]) -> Route {
+        let route = match route {
+            Route::Template {
+                prompt,
+                system,
+                route,
+            } => {
+                request.set_prompt(&prompt);
+                if let Some(system) = &system {
+                    request.set_system(system);
+                }
+                *route
+            }
+            route => route,
+        };
+        for attachment in attachments {
+            request.add_attachment(attachment);
+        }
+        route
+    }
+
+    /// CAG agent for given corpus, reused while the corpus does not change so that its
+    /// documents are loaded only once per chat.
+    fn cag_agent(&mut self, corpus: &str) -> Result<&CagAgent> {
+        if self.cag.as_ref().is_none_or(|cag| cag.corpus() != corpus) {
+            self.cag = Some(CagAgent::new(self.backend.clone(), corpus)?);
+        }
+        Ok(self.cag.as_ref().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::attachment::Attachment;
+    use crate::chat::Chat;
+    use crate::router::Route;
+    use crate::slm::SlmRequest;
+
+    #[test]
+    fn prepare() {
+        let path = std::env::temp_dir().join(format!("jarvis-chat-{}.txt", std::process::id()));
+        fs::write(&path, "attached text\n").unwrap();
+        let attachments =
+            [Attachment::from_file(path.to_str().unwrap(), Attachment::LIMIT).unwrap()];
+        fs::remove_file(&path).unwrap();
+        let name = attachments[0].name().to_string();
+
+        let mut request = SlmRequest::new("summarize");
+        let route = Route::Template {
+            prompt: "Summarize in one line".to_string(),
+            system: Some("Be brief".to_string()),
+            route: Box::new(Route::Rag("notes".to_string())),
+        };
+        let route = Chat::prepare(&mut request, route, &attachments);
+        assert!(matches!(route, Route::Rag(context) if context == "notes"));
+        assert_eq!(
+            request.prompt(),
+            format!("Summarize in one line\n\n--- {name} ---\nattached text")
+        );
+        assert_eq!(request.system(), Some("Be brief"));
+
+        let mut request = SlmRequest::new("summarize");
+        let route = Chat::prepare(&mut request, Route::Prompt, &attachments);
+        assert!(matches!(route, Route::Prompt));
+        assert_eq!(
+            request.prompt(),
+            format!("summarize\n\n--- {name} ---\nattached text")
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::rc::Rc;
+
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::command::Command;
+use crate::config;
+use crate::dictionary::{
+    self,
+    render::{self, Labels},
+};
+use crate::error::Result;
+use crate::slm::{SlmBackend, SlmRequest};
+
+/// Verb conjugation, from the inflected forms of its dictionary definition or from SLM when
+/// the dictionary has none.
+pub struct ConjugateCommand {
+    verb: String,
+}
+
+impl ConjugateCommand {
+    const GRAMMARIAN_SYSTEM: &'static str = "You are a grammarian. Conjugate the verb given by \
+    user, in the language of the verb, for its main moods and tenses. Respond with a Markdown \
+    table for each tense, with person and form columns, without any other text.";
+
+    pub fn pattern() -> Regex {
+        Regex::new(r"(?i)^conjugate\s+(?:the\s+verb\s+)?(.*?\S)$").unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("ConjugateCommand::parse(captures: Captures) -> Command");
+        let verb = captures[1].to_string();
+        debug!("verb: {verb}");
+        Command::ConjugateCommand(Self { verb })
+    }
+
+    pub async fn exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>> {
+        trace!(
+            "ConjugateCommand::exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>>"
+        );
+        let definitions = dictionary::available_entries(&self.verb).await;
+        if let Some(definition) = definitions
+            .iter()
+            .find(|definition| !definition.forms.is_empty())
+        {
+            let mut lines = vec![
+                format!("| | {} |", definition.word),
+                "|---|---|".to_string(),
+            ];
+            for form in &definition.forms {
+                lines.push(format!("| {} | {} |", form.label, form.form));
+            }
+            return Ok(Some(lines.join("\n")));
+        }
+
+        let mut request = SlmRequest::new(&self.verb);
+        request.set_system(Self::GRAMMARIAN_SYSTEM);
+        let reply = backend.complete(request).await?;
+        let config = config::get_config().dictionary.clone().unwrap_or_default();
+        Ok(Some(render::generated(&reply, &Labels::new(&config))))
+    }
+}
@@ -0,0 +1,256 @@
+use std::{env, fs, process, slice};
+
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::command::Command;
+use crate::dictionary::{self, Definition, Example, Expression, Meaning};
+use crate::error::{AppError, Result};
+use crate::util::console;
+
+/// Dictionary editing: add a definition interactively or from a YAML/JSON file, edit the
+/// definitions of a word in external editor and remove them.
+pub struct DictCommand {
+    action: String,
+    word: String,
+    file: Option<String>,
+    yes: bool,
+}
+
+impl DictCommand {
+    const EDITOR: &'static str = "vi";
+
+    pub fn pattern() -> Regex {
+        Regex::new(
+            r"(?i)^dict\s+(add|edit|remove)\s+(.*?\S)(?:\s+from\s+(\S+))?(?:\s+(--yes|-y))?$",
+        )
+        .unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("DictCommand::parse(captures: Captures) -> Command");
+        let action = captures[1].to_lowercase();
+        let word = captures[2].to_string();
+        let file = captures.get(3).map(|m| m.as_str().to_string());
+        let yes = captures.get(4).is_some();
+        debug!("action: {action}, word: {word}, file: {file:?}");
+        Command::DictCommand(Self {
+            action,
+            word,
+            file,
+            yes,
+        })
+    }
+
+    pub async fn exec(&self) -> Result<Option<String>> {
+        trace!("DictCommand::exec(&self) -> Result<Option<String>>");
+        match self.action.as_str() {
+            "add" => self.add().await,
+            "edit" => self.edit().await,
+            "remove" => self.remove().await,
+            _ => Ok(None),
+        }
+    }
+
+    async fn add(&self) -> Result<Option<String>> {
+        trace!("DictCommand::add(&self) -> Result<Option<String>>");
+        let definitions = match &self.file {
+            Some(file) => dictionary::parse_definitions(&fs::read_to_string(file)?)?,
+            None => vec![self.ask()?],
+        };
+        let definitions = Self::checked(&self.word, definitions)?;
+        let count = dictionary::store()?.import(definitions).await?;
+        Ok(Some(format!("Added {count} definitions of {}", self.word)))
+    }
+
+    /// Review the word definitions in external editor and replace them with the edited ones.
+    async fn edit(&self) -> Result<Option<String>> {
+        trace!("DictCommand::edit(&self) -> Result<Option<String>>");
+        let store = dictionary::store()?;
+        let definitions = store.lookup(slice::from_ref(&self.word)).await?;
+        if definitions.is_empty() {
+            return Err(AppError::Dictionary(format!(
+                "no definition of {}, use dict add",
+                self.word
+            )));
+        }
+
+        let message = Self::review(&self.word, &definitions, async |edited| {
+            if edited == definitions {
+                return Ok(format!("No changes to {}", self.word));
+            }
+            let count = store.replace(&self.word, edited).await?;
+            Ok(format!("Saved {count} definitions of {}", self.word))
+        })
+        .await?;
+        Ok(Some(message))
+    }
+
+    /// Open definitions as YAML in `$VISUAL` or `$EDITOR` and save the edited ones, checked
+    /// against the word. If these are not valid or cannot be saved the file is kept so that
+    /// changes are not lost.
+    pub async fn review<T>(
+        word: &str,
+        definitions: &[Definition],
+        save: impl AsyncFnOnce(Vec<Definition>) -> Result<T>,
+    ) -> Result<T> {
+        trace!(
+            "DictCommand::review<T>(word: &str, definitions: &[Definition], save: impl AsyncFnOnce(Vec<Definition>) -> Result<T>) -> Result<T>"
+        );
+        let path = env::temp_dir().join(format!("jarvis-dict-{}.yml", process::id()));
+        fs::write(&path, serde_yaml::to_string(definitions)?)?;
+        let editor = env::var("VISUAL")
+            .or_else(|_| env::var("EDITOR"))
+            .unwrap_or_else(|_| Self::EDITOR.to_string());
+        // editor variable may hold arguments too, e.g. `code --wait`
+        let mut editor = editor.split_whitespace();
+        let status = tokio::process::Command::new(editor.next().unwrap_or(Self::EDITOR))
+            .args(editor)
+            .arg(&path)
+            .status()
+            .await?;
+        if !status.success() {
+            return Err(AppError::Dictionary(format!(
+                "editor exited with {status}, changes kept in {}",
+                path.display()
+            )));
+        }
+
+        let kept =
+            |e: AppError| AppError::Dictionary(format!("{e}, changes kept in {}", path.display()));
+        let edited = fs::read_to_string(&path)?;
+        let edited = dictionary::parse_definitions(&edited)
+            .and_then(|edited| Self::checked(word, edited))
+            .map_err(kept)?;
+        let saved = save(edited).await.map_err(kept)?;
+        fs::remove_file(&path)?;
+        Ok(saved)
+    }
+
+    async fn remove(&self) -> Result<Option<String>> {
+        trace!("DictCommand::remove(&self) -> Result<Option<String>>");
+        let store = dictionary::store()?;
+        let count = store.lookup(slice::from_ref(&self.word)).await?.len();
+        if count == 0 {
+            return Ok(Some(format!("No definition of {}", self.word)));
+        }
+        let question = format!("Remove {count} definitions of {}?", self.word);
+        if !self.yes && !console::confirm(&question)? {
+            return Ok(Some("Remove cancelled".to_string()));
+        }
+        let count = store.remove(&self.word).await?;
+        Ok(Some(format!(
+            "Removed {count} definitions of {}",
+            self.word
+        )))
+    }
+
+    /// Normalize and validate definitions, taking the command word for missing headwords and
+    /// rejecting other words, so that edits stay within the word.
+    fn checked(word: &str, mut definitions: Vec<Definition>) -> Result<Vec<Definition>> {
+        trace!(
+            "DictCommand::checked(word: &str, definitions: Vec<Definition>) -> Result<Vec<Definition>>"
+        );
+        if definitions.is_empty() {
+            return Err(AppError::Dictionary(format!("no definition of {word}")));
+        }
+        for definition in &mut definitions {
+            if definition.word.trim().is_empty() {
+                definition.word = word.to_string();
+            }
+            definition.normalize();
+            if definition.word != word.trim() {
+                return Err(AppError::Dictionary(format!(
+                    "definition of {} given for {}",
+                    definition.word, word
+                )));
+            }
+            definition.validate()?;
+        }
+        Ok(definitions)
+    }
+
+    /// Read definition from terminal; empty answer ends a list.
+    fn ask(&self) -> Result<Definition> {
+        trace!("DictCommand::ask(&self) -> Result<Definition>");
+        if !console::interactive() {
+            return Err(AppError::Dictionary(format!(
+                "not a terminal, use dict add {} from <file>",
+                self.word
+            )));
+        }
+        let mut definition = Definition::new(&self.word);
+        definition.part_of_speech = Some(console::ask("Part of speech")?);
+        loop {
+            let numbering = definition.meanings.len() + 1;
+            let text = console::ask(&format!("Meaning {numbering} (empty to end)"))?;
+            if text.is_empty() {
+                break;
+            }
+            let mut meaning = Meaning {
+                definition: text,
+                examples: Vec::new(),
+            };
+            loop {
+                let text = console::ask("  Example (empty to end)")?;
+                if text.is_empty() {
+                    break;
+                }
+                let source = console::ask("  Source")?;
+                meaning.examples.push(Example {
+                    text,
+                    source: Some(source),
+                });
+            }
+            definition.meanings.push(meaning);
+        }
+        loop {
+            let phrase = console::ask("Expression (empty to end)")?;
+            if phrase.is_empty() {
+                break;
+            }
+            let text = console::ask("  Definition")?;
+            definition.expressions.push(Expression {
+                phrase,
+                definition: text,
+            });
+        }
+        Ok(definition)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::command::dict::DictCommand;
+    use crate::dictionary;
+
+    #[test]
+    fn checked() {
+        let yaml = "
+part_of_speech: ' Substantiv '
+meanings:
+  - definition: Teritoriu locuit de un popor.
+    examples:
+      - text: Țara mea.
+        source: ''
+";
+        let definitions = dictionary::parse_definitions(yaml).unwrap();
+        let definitions = DictCommand::checked("țară", definitions).unwrap();
+        assert_eq!(definitions[0].word, "țară");
+        assert_eq!(definitions[0].key, "tara");
+        assert_eq!(definitions[0].part_of_speech.as_deref(), Some("Substantiv"));
+        assert_eq!(definitions[0].meanings[0].examples[0].source, None);
+
+        let other = dictionary::parse_definitions(r#"[{"word": "casă", "expressions": []}]"#);
+        let error = DictCommand::checked("țară", other.unwrap()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Dictionary error: definition of casă given for țară"
+        );
+        let empty = dictionary::parse_definitions("meanings: [{definition: ' '}]").unwrap();
+        assert_eq!(
+            DictCommand::checked("țară", empty).unwrap_err().to_string(),
+            "Dictionary error: invalid definition of țară: meaning 1 has empty definition"
+        );
+    }
+}
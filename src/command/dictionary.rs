@@ -1,123 +1,258 @@
-use futures::StreamExt;
-use log::trace;
-use mongodb::bson::doc;
-use mongodb::options::FindOptions;
-use mongodb::{Client, Collection};
+use std::rc::Rc;
+
+use log::{debug, trace, warn};
 use regex::{Captures, Regex};
-use serde::{Deserialize, Serialize};
 
-use crate::command::Command;
-use crate::error::Result;
+use crate::command::{Command, dict::DictCommand};
+use crate::config::{self, DictionaryConfig};
+use crate::dictionary::render::{self, Format, Labels};
+use crate::dictionary::{self, Definition, DictionaryStore, Lookup};
+use crate::error::{AppError, Result};
+use crate::slm::{SlmBackend, SlmRequest};
+use crate::util::console;
 
 pub struct DictionaryCommand {
     word: String,
+    mode: Mode,
+    limit: usize,
+    format: Format,
+}
+
+/// Matching mode selected by command option; ranked lookup is the default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// Exact and diacritic-insensitive headword matches first, then expressions and full text
+    /// search results.
+    Ranked,
+    /// Headword matches only, ignoring case and diacritics.
+    Exact,
+    Prefix,
+    Fuzzy,
 }
 
 impl DictionaryCommand {
-    const CONNECTION_URL: &'static str = "mongodb://localhost:27017";
-    const DATABASE: &'static str = "kb";
-    const COLLECTION: &'static str = "data";
+    const LIMIT: usize = 10;
+    const SUGGESTIONS: usize = 5;
+    const LEXICOGRAPHER_SYSTEM: &'static str = "You are a lexicographer. Write the dictionary \
+    entry of the word given by user, in the language of the word. Respond with a single JSON \
+    object, without any other text, of the form:\n\
+    {\"word\": \"<word>\", \"part_of_speech\": \"<part of speech>\", \"meanings\": \
+    [{\"definition\": \"<meaning>\", \"examples\": [{\"text\": \"<usage example>\", \
+    \"source\": null}]}], \"expressions\": [{\"phrase\": \"<expression>\", \
+    \"definition\": \"<meaning of expression>\"}]}\n\
+    List the meanings from the most common one. Do not make up sources for examples and leave \
+    expressions empty if the word has no common ones.";
+
+    /// Ranked lookup rendered as Markdown, e.g. for tools and other commands.
+    pub fn new(word: &str) -> Self {
+        Self {
+            word: word.to_string(),
+            mode: Mode::Ranked,
+            limit: Self::LIMIT,
+            format: Format::Markdown,
+        }
+    }
 
     pub fn pattern() -> Regex {
-        Regex::new(r"^(?i)\b(?:def|define)\s+(?:of\s+)?(.+)\b$").unwrap()
+        Regex::new(
+            r"^(?i)\b(?:def|define)\s+(?:of\s+)?(.*?\w)\b((?:\s+--(?:exact|prefix|fuzzy|limit\s+\d+|format\s+\w+))*)$",
+        )
+        .unwrap()
     }
 
     pub fn parse(captures: Captures) -> Command {
         trace!("DictionaryCommand::parse(captures: Captures) -> Command");
-        let word = captures[1].to_string();
-        let command = DictionaryCommand { word };
+        let mut command = DictionaryCommand::new(&captures[1]);
+        command.format = Format::detect();
+        let options = Regex::new(r"--(\w+)(?:\s+(\w+))?").unwrap();
+        for option in options.captures_iter(&captures[2]) {
+            match option[1].to_lowercase().as_str() {
+                "exact" => command.mode = Mode::Exact,
+                "prefix" => command.mode = Mode::Prefix,
+                "fuzzy" => command.mode = Mode::Fuzzy,
+                "limit" => command.limit = option[2].parse().unwrap_or(Self::LIMIT),
+                _ => {
+                    if let Some(format) = Format::from_name(&option[2]) {
+                        command.format = format;
+                    }
+                }
+            }
+        }
+        debug!(
+            "word: {}, mode: {:?}, limit: {}, format: {:?}",
+            command.word, command.mode, command.limit, command.format
+        );
         Command::DictionaryCommand(command)
     }
 
-    pub async fn exec(&self) -> Result<Option<String>> {
-        trace!("DictionaryCommand::exec(&self) -> exec(&self) -> Result<Option<String>>");
-        let client = Client::with_uri_str(Self::CONNECTION_URL).await?;
-        let database = client.database(Self::DATABASE);
-        let collection: Collection<Definition> = database.collection(Self::COLLECTION);
-
-        let filter = doc! {"$text": {"$search": &self.word}};
-        let options = FindOptions::builder().build();
-
-        let mut cursor = collection.find(filter, options).await?;
-        let mut response = Vec::<String>::new();
-        while let Some(result) = cursor.next().await {
-            match result {
-                Ok(definition) => {
-                    if !definition.meanings.is_empty() {
-                        response.push(String::new());
-                        response.push(match definition.part_of_speech {
-                            Some(part_of_speech) => {
-                                format!("# {}, {}", definition.word, part_of_speech.to_lowercase())
-                            }
-                            None => format!("# {}", definition.word),
-                        });
-                        response.push(String::new());
-
-                        let mut numbering = 1;
-                        for meaning in definition.meanings {
-                            response.push(format!("{}. {}", numbering, meaning.definition));
-                            response.push(String::new());
-
-                            for example in meaning.examples {
-                                response.push(format!("> {}  ", example.text));
-                                if let Some(source) = example.source {
-                                    response.push(format!("\u{2014} _{}_", source));
-                                }
-                                response.push(String::new());
-                            }
-                            numbering = numbering + 1;
-                        }
-
-                        if !definition.expressions.is_empty() {
-                            response.push(String::from("## În expresie"));
-                        }
-                    }
-
-                    for expression in &definition.expressions {
-                        response.push(format!(
-                            "- __{}__: {}",
-                            expression.phrase, expression.definition
-                        ));
-                    }
-                    if !definition.expressions.is_empty() {
-                        response.push(String::new());
-                    }
-                }
-                Err(e) => eprintln!("Error loading document: {}", e),
+    /// Render lookup; if the word is neither in dictionary nor close to a headword the
+    /// definition is generated by SLM and, on terminal, user may review and save it. If the
+    /// dictionary store is not available the definition is generated too, without saving.
+    pub async fn exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>> {
+        trace!(
+            "DictionaryCommand::exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>>"
+        );
+        let (mut lookup, available) = match self.lookup().await {
+            Ok(lookup) => (lookup, true),
+            Err(e) => {
+                warn!("dictionary not available, answering without it: {e}");
+                let lookup = Lookup {
+                    word: self.word.clone(),
+                    definitions: Vec::new(),
+                    more: 0,
+                    suggestions: Vec::new(),
+                    generated: false,
+                };
+                (lookup, false)
             }
+        };
+        if !lookup.definitions.is_empty() || !lookup.suggestions.is_empty() {
+            return Ok(Some(self.render(&lookup)?));
         }
 
-        if response.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(response.join("\n")))
+        let config = config::get_config().dictionary.clone().unwrap_or_default();
+        lookup
+            .definitions
+            .push(self.generate(backend, &config).await?);
+        lookup.generated = true;
+        let response = self.render(&lookup)?;
+        if !available || !console::interactive() {
+            return Ok(Some(response));
         }
+        println!("{response}");
+        let answer = console::ask("Save generated definition to dictionary? [y/e(dit)/N]")?;
+        let store = dictionary::store()?;
+        let count = match answer.to_lowercase().as_str() {
+            "y" | "yes" => store.import(lookup.definitions).await?,
+            "e" | "edit" => {
+                DictCommand::review(&self.word, &lookup.definitions, async |edited| {
+                    store.import(edited).await
+                })
+                .await?
+            }
+            _ => return Ok(Some("Generated definition not saved".to_string())),
+        };
+        Ok(Some(format!("Saved {count} definitions of {}", self.word)))
     }
-}
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Definition {
-    word: String,
-    key: String,
-    part_of_speech: Option<String>,
-    meanings: Vec<Meaning>,
-    expressions: Vec<Expression>,
-}
+    /// Lookup in command format, with configured labels.
+    pub fn render(&self, lookup: &Lookup) -> Result<String> {
+        trace!("DictionaryCommand::render(&self, lookup: &Lookup) -> Result<String>");
+        let config = config::get_config().dictionary.clone().unwrap_or_default();
+        render::render(lookup, self.format, &Labels::new(&config))
+    }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Expression {
-    phrase: String,
-    definition: String,
+    /// Definitions found, limited, or suggestions if there are none.
+    pub async fn lookup(&self) -> Result<Lookup> {
+        trace!("DictionaryCommand::lookup(&self) -> Result<Lookup>");
+        let store = dictionary::store()?;
+        let (mut definitions, skipped) = self.find(store.as_ref()).await?;
+        let suggestions = match definitions.is_empty() {
+            true => self.suggest(store.as_ref()).await?,
+            false => Vec::new(),
+        };
+        let more = skipped + definitions.len().saturating_sub(self.limit);
+        definitions.truncate(self.limit);
+        Ok(Lookup {
+            word: self.word.clone(),
+            definitions,
+            more,
+            suggestions,
+            generated: false,
+        })
+    }
+
+    /// Definitions for selected mode, best match first, and the number of matched headwords
+    /// not looked up because of limit.
+    async fn find(&self, store: &dyn DictionaryStore) -> Result<(Vec<Definition>, usize)> {
+        trace!(
+            "DictionaryCommand::find(&self, store: &dyn DictionaryStore) -> Result<(Vec<Definition>, usize)>"
+        );
+        let mut headwords = match self.mode {
+            Mode::Ranked => {
+                let definitions = store.search(&self.word).await?;
+                return Ok((dictionary::rank(&self.word, definitions), 0));
+            }
+            Mode::Exact => return Ok((dictionary::entries(store, &self.word).await?, 0)),
+            Mode::Prefix => dictionary::prefixed(&self.word, &store.headwords().await?),
+            Mode::Fuzzy => dictionary::similar(&self.word, &store.headwords().await?),
+        };
+        let skipped = headwords.len().saturating_sub(self.limit);
+        headwords.truncate(self.limit);
+        // store returns definitions in no particular order; keep the one of matched headwords
+        let mut definitions = store.lookup(&headwords).await?;
+        definitions.sort_by_key(|definition| {
+            headwords
+                .iter()
+                .position(|headword| *headword == definition.word)
+        });
+        Ok((definitions, skipped))
+    }
+
+    /// Definition written by SLM with lexicographer system prompt, normalized and validated as
+    /// the ones added by user; the headword is always the looked up word.
+    async fn generate(
+        &self,
+        backend: &Rc<dyn SlmBackend>,
+        config: &DictionaryConfig,
+    ) -> Result<Definition> {
+        trace!(
+            "DictionaryCommand::generate(&self, backend: &Rc<dyn SlmBackend>, config: &DictionaryConfig) -> Result<Definition>"
+        );
+        let mut request = SlmRequest::new(&self.word);
+        request.set_system(
+            config
+                .system
+                .as_deref()
+                .unwrap_or(Self::LEXICOGRAPHER_SYSTEM),
+        );
+
+        let reply = backend.complete(request).await?;
+        debug!("generated definition: {reply}");
+        let invalid =
+            || AppError::Dictionary(format!("SLM reply is not a definition of {}", self.word));
+        let json = json_object(&reply).ok_or_else(invalid)?;
+        let mut definition = dictionary::parse_definitions(json)
+            .map_err(|_| invalid())?
+            .into_iter()
+            .next()
+            .ok_or_else(invalid)?;
+        definition.word = self.word.clone();
+        definition.normalize();
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// Headwords close to the word, closest first.
+    async fn suggest(&self, store: &dyn DictionaryStore) -> Result<Vec<String>> {
+        trace!(
+            "DictionaryCommand::suggest(&self, store: &dyn DictionaryStore) -> Result<Vec<String>>"
+        );
+        let mut suggestions = dictionary::similar(&self.word, &store.headwords().await?);
+        suggestions.truncate(Self::SUGGESTIONS);
+        Ok(suggestions)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Example {
-    text: String,
-    source: Option<String>,
+/// Outermost JSON object in SLM reply, that models often wrap in code fences or prose.
+fn json_object(reply: &str) -> Option<&str> {
+    let start = reply.find('{')?;
+    let end = reply.rfind('}')?;
+    (start < end).then(|| &reply[start..=end])
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Meaning {
-    definition: String,
-    examples: Vec<Example>,
+#[cfg(test)]
+mod test {
+    use crate::command::dictionary::json_object;
+
+    #[test]
+    fn reply_json() {
+        let reply = "Iată definiția:\n```json\n{\"word\": \"dor\", \"meanings\": [{\"definition\": \"Nostalgie.\"}]}\n```";
+        assert_eq!(
+            json_object(reply),
+            Some(r#"{"word": "dor", "meanings": [{"definition": "Nostalgie."}]}"#)
+        );
+        assert_eq!(json_object("} nothing {"), None);
+        assert_eq!(json_object("nothing"), None);
+    }
 }
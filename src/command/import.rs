@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::command::Command;
+use crate::dictionary;
+use crate::error::Result;
+
+/// Load a dictionary dump, JSON array or JSON lines, into the configured dictionary store.
+/// Prompts must name both the dump and the dictionary, e.g. `import words.jsonl into
+/// dictionary` or `import dictionary from words.jsonl`, and the dump must exist; other prompts
+/// starting with import are left to the SLM.
+pub struct ImportCommand {
+    path: String,
+}
+
+impl ImportCommand {
+    pub fn pattern() -> Regex {
+        Regex::new(
+            r"(?i)^import\s+(?:dictionary\s+from\s+(\S+)|(\S+)\s+into\s+(?:the\s+)?dictionary)$",
+        )
+        .unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("ImportCommand::parse(captures: Captures) -> Command");
+        let path = captures
+            .get(1)
+            .or_else(|| captures.get(2))
+            .map_or("", |m| m.as_str())
+            .to_string();
+        debug!("path: {path}");
+        Command::ImportCommand(Self { path })
+    }
+
+    pub async fn exec(&self) -> Result<Option<String>> {
+        trace!("ImportCommand::exec(&self) -> Result<Option<String>>");
+        if !Path::new(&self.path).is_file() {
+            debug!("dump {} not found, prompt left to SLM", self.path);
+            return Ok(None);
+        }
+        let definitions = dictionary::read_dump(Path::new(&self.path))?;
+        let count = dictionary::store()?.import(definitions).await?;
+        Ok(Some(format!(
+            "Imported {count} definitions from {}",
+            self.path
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::command::{Command, import::ImportCommand};
+
+    fn path(prompt: &str) -> Option<String> {
+        let captures = ImportCommand::pattern().captures(prompt)?;
+        match ImportCommand::parse(captures) {
+            Command::ImportCommand(command) => Some(command.path),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn pattern() {
+        assert_eq!(
+            path("import words.jsonl into dictionary").unwrap(),
+            "words.jsonl"
+        );
+        assert_eq!(
+            path("Import dex.json into the dictionary").unwrap(),
+            "dex.json"
+        );
+        assert_eq!(
+            path("import dictionary from /tmp/dex.json").unwrap(),
+            "/tmp/dex.json"
+        );
+        assert!(path("import numpy").is_none());
+        assert!(path("import pandas as pd").is_none());
+        assert!(path("import words.jsonl into notes").is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_dump() {
+        let command = ImportCommand {
+            path: "/nonexistent/words.jsonl".to_string(),
+        };
+        assert!(command.exec().await.unwrap().is_none());
+    }
+}
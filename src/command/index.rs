@@ -0,0 +1,40 @@
+use std::rc::Rc;
+
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::command::Command;
+use crate::error::Result;
+use crate::index::Index;
+use crate::slm::SlmBackend;
+
+pub struct IndexCommand {
+    name: String,
+    paths: Vec<String>,
+}
+
+impl IndexCommand {
+    pub fn pattern() -> Regex {
+        Regex::new(r"(?i)^index\s+([\w.-]+)\s+from\s+(.+)$").unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("IndexCommand::parse(captures: Captures) -> Command");
+        let name = captures[1].to_string();
+        let paths = captures[2].split_whitespace().map(str::to_string).collect();
+        debug!("name: {name}, paths: {paths:?}");
+        Command::IndexCommand(Self { name, paths })
+    }
+
+    pub async fn exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>> {
+        trace!("IndexCommand::exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>>");
+        let index = Index::build(&self.name, &self.paths, backend.as_ref()).await?;
+        index.save()?;
+        Ok(Some(format!(
+            "Indexed {} chunks from {} files into {}",
+            index.chunks_count(),
+            index.sources_count(),
+            self.name
+        )))
+    }
+}
@@ -1,22 +1,54 @@
+pub mod conjugate;
+pub mod dict;
 pub mod dictionary;
+pub mod import;
+pub mod index;
 pub mod server;
+pub mod session;
+pub mod status;
+pub mod thesaurus;
+pub mod translate;
+pub mod user;
+pub mod wake;
 
-use std::str::FromStr;
+use std::{rc::Rc, str::FromStr};
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::{
+    command::conjugate::ConjugateCommand,
+    command::dict::DictCommand,
     command::dictionary::DictionaryCommand,
+    command::import::ImportCommand,
+    command::index::IndexCommand,
     command::server::ServerCommand,
+    command::session::SessionCommand,
+    command::status::StatusCommand,
+    command::thesaurus::ThesaurusCommand,
+    command::translate::TranslateCommand,
+    command::user::UserCommand,
+    command::wake::WakeCommand,
     error::{AppError, Result},
+    slm::SlmBackend,
 };
 
 pub type CommandBuilder = fn(regex::Captures) -> Command;
 
+#[allow(clippy::enum_variant_names)]
 pub enum Command {
+    ConjugateCommand(ConjugateCommand),
+    DictCommand(DictCommand),
     DictionaryCommand(DictionaryCommand),
+    ImportCommand(ImportCommand),
+    IndexCommand(IndexCommand),
     ServerCommand(ServerCommand),
+    SessionCommand(SessionCommand),
+    StatusCommand(StatusCommand),
+    ThesaurusCommand(ThesaurusCommand),
+    TranslateCommand(TranslateCommand),
+    UserCommand(UserCommand),
+    WakeCommand(WakeCommand),
 }
 
 impl FromStr for Command {
@@ -33,17 +65,54 @@ impl FromStr for Command {
 }
 
 impl Command {
-    pub async fn exec(&self) -> Result<Option<String>> {
+    pub fn name(&self) -> &'static str {
         match self {
-            Command::DictionaryCommand(command) => command.exec().await,
+            Command::ConjugateCommand(_) => "conjugate",
+            Command::DictCommand(_) => "dict",
+            Command::DictionaryCommand(_) => "dictionary",
+            Command::ImportCommand(_) => "import",
+            Command::IndexCommand(_) => "index",
+            Command::ServerCommand(_) => "server",
+            Command::SessionCommand(_) => "session",
+            Command::StatusCommand(_) => "status",
+            Command::ThesaurusCommand(_) => "thesaurus",
+            Command::TranslateCommand(_) => "translate",
+            Command::UserCommand(_) => "user",
+            Command::WakeCommand(_) => "wake",
+        }
+    }
+
+    pub async fn exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>> {
+        match self {
+            Command::ConjugateCommand(command) => command.exec(backend).await,
+            Command::DictCommand(command) => command.exec().await,
+            Command::DictionaryCommand(command) => command.exec(backend).await,
+            Command::ImportCommand(command) => command.exec().await,
+            Command::IndexCommand(command) => command.exec(backend).await,
             Command::ServerCommand(command) => command.exec().await,
+            Command::SessionCommand(command) => command.exec().await,
+            Command::StatusCommand(command) => command.exec().await,
+            Command::ThesaurusCommand(command) => command.exec(backend).await,
+            Command::TranslateCommand(command) => command.exec(backend).await,
+            Command::UserCommand(command) => command.exec().await,
+            Command::WakeCommand(command) => command.exec().await,
         }
     }
 }
 
 lazy_static! {
     static ref COMMAND_REGEX: Vec<(Regex, CommandBuilder)> = vec![
+        (DictCommand::pattern(), DictCommand::parse),
         (DictionaryCommand::pattern(), DictionaryCommand::parse),
+        (ThesaurusCommand::pattern(), ThesaurusCommand::parse),
+        (TranslateCommand::pattern(), TranslateCommand::parse),
+        (ConjugateCommand::pattern(), ConjugateCommand::parse),
+        (ImportCommand::pattern(), ImportCommand::parse),
+        (IndexCommand::pattern(), IndexCommand::parse),
+        // before server command, which also accepts a bare status for the default server
+        (StatusCommand::pattern(), StatusCommand::parse),
         (ServerCommand::pattern(), ServerCommand::parse),
+        (SessionCommand::pattern(), SessionCommand::parse),
+        (WakeCommand::pattern(), WakeCommand::parse),
     ];
 }
@@ -1,22 +1,44 @@
-use std::io::{Write, stdout};
+use std::{
+    collections::BTreeMap,
+    io::{Write, stdout},
+    time::Duration,
+};
 
 use log::{debug, trace};
 use regex::{Captures, Regex};
 use reqwest::Client;
 
 use crate::command::Command;
+use crate::config::{self, Server, ServerConfig};
 use crate::error::Result;
-use crate::util::net;
+use crate::lifecycle::{Lifecycle, State};
+use crate::util::{
+    console,
+    net::{self, Probe},
+};
 
 pub struct ServerCommand {
     command: String,
     sub_command: Option<String>,
+    server: Option<String>,
+    yes: bool,
 }
 
 impl ServerCommand {
+    const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+    pub fn new(command: &str, sub_command: Option<&str>, server: Option<&str>) -> Self {
+        Self {
+            command: command.to_string(),
+            sub_command: sub_command.map(str::to_string),
+            server: server.map(str::to_string),
+            yes: false,
+        }
+    }
+
     pub fn pattern() -> Regex {
         Regex::new(
-            r"(?i)^(server|history|start|stop|wake-up|clear|shutdown|sleep|status)(?:\s+(status|start|stop|clear|reset|history))?$",
+            r"(?i)^(server|history|start|stop|wake-up|clear|shutdown|sleep|status)(?:\s+(status|start|stop|clear|reset|history))?(?:\s+(\w[\w.-]*))?(?:\s+(--yes|-y))?$",
         )
         .unwrap()
     }
@@ -25,56 +47,74 @@ impl ServerCommand {
         trace!("ServerCommand::parse(captures: Captures) -> Command");
         let command = captures[1].to_string();
         let sub_command = captures.get(2).map(|m| m.as_str().to_string());
-        debug!("command: {command}, sub_command: {sub_command:?}");
+        let server = captures.get(3).map(|m| m.as_str().to_string());
+        let yes = captures.get(4).is_some();
+        debug!("command: {command}, sub_command: {sub_command:?}, server: {server:?}");
         Command::ServerCommand(Self {
             command,
             sub_command,
+            server,
+            yes,
         })
     }
 
     pub async fn exec(&self) -> Result<Option<String>> {
         trace!("ServerCommand::exec(&self) -> Result<Option<String>>");
+        let config = config::get_config();
+        if !self.targets_server(&config.servers) {
+            debug!("{} is not a server command", self.command);
+            return Ok(None);
+        }
+        let server = config.server(self.server.as_deref())?;
         if let Some(sub_command) = &self.sub_command {
             match (self.command.as_str(), sub_command.as_str()) {
-                ("server", "start") => self.server_start().await,
-                ("server", "stop") => self.server_stop().await,
-                ("server", "status") => self.server_status(),
-                ("history", "clear" | "reset") | ("clear", "history") => self.history_reset().await,
+                ("server", "start") => self.server_start(&server).await,
+                ("server", "stop") => self.server_stop(&server).await,
+                ("server", "status") => self.server_status(&server).await,
+                ("history", "clear" | "reset") | ("clear", "history") => {
+                    self.history_reset(&server).await
+                }
                 _ => Ok(None),
             }
         } else {
             match self.command.as_str() {
-                "shutdown" | "sleep" | "stop" => self.server_stop().await,
-                "start" | "wake-up" => self.server_start().await,
-                "status" => self.server_status(),
+                "shutdown" | "sleep" | "stop" => self.server_stop(&server).await,
+                "start" | "wake-up" => self.server_start(&server).await,
+                "status" => self.server_status(&server).await,
                 _ => Ok(None),
             }
         }
     }
 
-    async fn server_start(&self) -> Result<Option<String>> {
-        trace!("ServerCommand::server_start(&self) -> Result<Option<String>>");
-        net::send_wol("10-7c-61-5f-10-be")?;
-
-        self.print("Waiting for server to start ");
-        for _ in 0..100 {
-            if net::connect_timeout("192.168.0.5", 22, 1000) {
-                break;
-            }
-            self.print(".");
-        }
-        println!();
-
-        self.print("Waiting for SLM service to start ");
-        for _ in 0..100 {
-            if net::connect_timeout("192.168.0.5", 1964, 500) {
-                break;
-            }
-            self.print(".");
+    /// Whether prompt addresses a server. After a bare verb, a word is a server name only if
+    /// configured, so that prompts like `stop it` or `status report`, and any bare verb without
+    /// servers, are left to SLM.
+    fn targets_server(&self, servers: &BTreeMap<String, ServerConfig>) -> bool {
+        if self.sub_command.is_some() || self.command == "server" {
+            return true;
         }
-        println!();
+        !servers.is_empty()
+            && self
+                .server
+                .as_ref()
+                .is_none_or(|name| servers.contains_key(name))
+    }
 
-        Ok(Some("SLM server and service started".to_string()))
+    async fn server_start(&self, server: &Server) -> Result<Option<String>> {
+        trace!("ServerCommand::server_start(&self, server: &Server) -> Result<Option<String>>");
+        Lifecycle::new(server)?
+            .start(|state, elapsed| {
+                self.print(&format!(
+                    "[{:>3}s] {} {state}\n",
+                    elapsed.as_secs(),
+                    server.name
+                ))
+            })
+            .await?;
+        Ok(Some(format!(
+            "{} server started and SLM model loaded",
+            server.name
+        )))
     }
 
     fn print(&self, s: &str) {
@@ -82,34 +122,96 @@ impl ServerCommand {
         let _ = stdout().flush();
     }
 
-    async fn server_stop(&self) -> Result<Option<String>> {
-        trace!("ServerCommand::server_stop(&self) -> Result<Option<String>>");
-        let url = "http://192.168.0.5:1964/shutdown";
-        let client = Client::new();
-        let _ = client.post(url).send().await;
-        Ok(Some(String::new()))
+    /// Shut server down after user confirmation, unless given `--yes`, and wait for it to go
+    /// down.
+    async fn server_stop(&self, server: &Server) -> Result<Option<String>> {
+        trace!("ServerCommand::server_stop(&self, server: &Server) -> Result<Option<String>>");
+        server.shutdown()?;
+        let lifecycle = Lifecycle::new(server)?;
+        if lifecycle.probe().await == State::Sleeping {
+            return Ok(Some(format!("{} server is already down", server.name)));
+        }
+
+        let in_flight = match lifecycle.in_flight().await {
+            Some(0) => "no requests in flight".to_string(),
+            Some(1) => "1 request in flight".to_string(),
+            Some(count) => format!("{count} requests in flight"),
+            None => "in-flight requests unknown".to_string(),
+        };
+        self.print(&format!("{} server has {in_flight}\n", server.name));
+        if !self.yes && !console::confirm(&format!("Shut down {} server?", server.name))? {
+            return Ok(Some("Shutdown cancelled".to_string()));
+        }
+
+        lifecycle
+            .stop(|state, elapsed| {
+                self.print(&format!(
+                    "[{:>3}s] {} {state}\n",
+                    elapsed.as_secs(),
+                    server.name
+                ))
+            })
+            .await?;
+        Ok(Some(format!("{} server is down", server.name)))
     }
 
-    fn server_status(&self) -> Result<Option<String>> {
-        trace!("ServerCommand::server_status(&self) -> Result<Option<String>>");
-        fn status(status: bool) -> &'static str {
-            match status {
-                true => "up",
-                false => "down",
+    async fn server_status(&self, server: &Server) -> Result<Option<String>> {
+        trace!("ServerCommand::server_status(&self, server: &Server) -> Result<Option<String>>");
+        fn status(probe: &Probe) -> String {
+            match probe.latency() {
+                Some(latency) => format!("up ({} ms)", latency.as_millis()),
+                None => format!("down ({})", probe.outcome),
             }
         }
-        let server_status = status(net::connect_timeout("192.168.0.5", 22, 100));
-        let slm_status = status(net::connect_timeout("192.168.0.5", 1964, 100));
-        let status = format!("- JARVIS server is {server_status}\n- SLM service is {slm_status}");
+        let probes = net::probe_all(&[
+            (&server.host, server.host_port, Self::PROBE_TIMEOUT),
+            (&server.host, server.service_port, Self::PROBE_TIMEOUT),
+        ])
+        .await;
+        let status = format!(
+            "- {} server is {}\n- SLM service is {}",
+            server.name,
+            status(&probes[0]),
+            status(&probes[1])
+        );
         Ok(Some(status))
     }
 
-    async fn history_reset(&self) -> Result<Option<String>> {
-        trace!("ServerCommand::history_reset(&self) -> Result<Option<String>>");
-        let url = "http://192.168.0.5:1964/history/clear";
-        let client = Client::new();
-        let response = client.post(url).send().await?;
+    async fn history_reset(&self, server: &Server) -> Result<Option<String>> {
+        trace!("ServerCommand::history_reset(&self, server: &Server) -> Result<Option<String>>");
+        let mut builder = Client::new().post(&server.history_url);
+        if let Some(token) = &server.api_token {
+            builder = builder.bearer_auth(token);
+        }
+        let response = builder.send().await?;
         debug!("history reset response: {response:?}");
         Ok(Some(String::new()))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::command::Command;
+    use crate::config::AppConfig;
+
+    #[test]
+    fn targets_server() {
+        let none = AppConfig::default().servers;
+        let config: AppConfig =
+            serde_yaml::from_str("servers:\n  box:\n    host: 127.0.0.1\n").unwrap();
+        let servers = config.servers;
+        let command = |prompt: &str| match prompt.parse::<Command>() {
+            Ok(Command::ServerCommand(command)) => command,
+            _ => panic!("{prompt} not parsed as server command"),
+        };
+
+        for prompt in ["stop it", "status report", "sleep well"] {
+            assert!(!command(prompt).targets_server(&servers), "{prompt}");
+            assert!(!command(prompt).targets_server(&none), "{prompt}");
+        }
+        assert!(!command("stop").targets_server(&none));
+        assert!(command("stop").targets_server(&servers));
+        assert!(command("stop box").targets_server(&servers));
+        assert!(command("server status").targets_server(&none));
+    }
+}
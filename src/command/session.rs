@@ -0,0 +1,77 @@
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::command::Command;
+use crate::error::{AppError, Result};
+use crate::session::{self, Session};
+
+pub struct SessionCommand {
+    action: String,
+    name: Option<String>,
+    format: Option<String>,
+}
+
+impl SessionCommand {
+    pub fn pattern() -> Regex {
+        Regex::new(
+            r"(?i)^sessions?\s+(list|show|delete|remove|export)(?:\s+([\w.-]+))?(?:\s+--format\s+(md|markdown|json))?$",
+        )
+        .unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("SessionCommand::parse(captures: Captures) -> Command");
+        let action = captures[1].to_lowercase();
+        let name = captures.get(2).map(|m| m.as_str().to_string());
+        let format = captures.get(3).map(|m| m.as_str().to_lowercase());
+        debug!("action: {action}, name: {name:?}, format: {format:?}");
+        Command::SessionCommand(Self {
+            action,
+            name,
+            format,
+        })
+    }
+
+    pub async fn exec(&self) -> Result<Option<String>> {
+        trace!("SessionCommand::exec(&self) -> Result<Option<String>>");
+        match (self.action.as_str(), self.name.as_deref()) {
+            ("list", _) => self.list(),
+            ("show", Some(name)) => Ok(Some(session::markdown(Session::load(name)?.history()))),
+            ("delete" | "remove", Some(name)) => {
+                Session::delete(name)?;
+                Ok(Some(format!("Session {name} deleted")))
+            }
+            ("export", Some(name)) => self.export(name),
+            (action, None) => Err(AppError::Session(format!("{action} requires session name"))),
+            _ => Ok(None),
+        }
+    }
+
+    fn list(&self) -> Result<Option<String>> {
+        trace!("SessionCommand::list(&self) -> Result<Option<String>>");
+        let sessions = Session::list()?;
+        if sessions.is_empty() {
+            return Ok(Some("No sessions".to_string()));
+        }
+        let lines: Vec<String> = sessions
+            .iter()
+            .map(|session| {
+                format!(
+                    "- {} ({} messages)",
+                    session.name(),
+                    session.history().len()
+                )
+            })
+            .collect();
+        Ok(Some(lines.join("\n")))
+    }
+
+    fn export(&self, name: &str) -> Result<Option<String>> {
+        trace!("SessionCommand::export(&self, name: &str) -> Result<Option<String>>");
+        let session = Session::load(name)?;
+        match self.format.as_deref() {
+            Some("json") => Ok(Some(serde_json::to_string_pretty(session.history())?)),
+            _ => Ok(Some(session::markdown(session.history()))),
+        }
+    }
+}
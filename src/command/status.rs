@@ -0,0 +1,254 @@
+use std::time::{Duration, Instant};
+
+use futures::future::{self, FutureExt, LocalBoxFuture};
+use log::{debug, trace};
+use regex::{Captures, Regex};
+use reqwest::Client;
+use serde::Serialize;
+
+use crate::command::Command;
+use crate::config;
+use crate::error::Result;
+use crate::lifecycle::Lifecycle;
+use crate::slm::{self, ServiceStatus};
+use crate::util::net;
+
+/// Dashboard of every configured server and SLM backend, probed concurrently.
+pub struct StatusCommand {
+    json: bool,
+}
+
+/// Probe outcome for one server or backend; service fields are empty when not exposed.
+#[derive(Serialize)]
+struct TargetStatus {
+    name: String,
+    kind: String,
+    address: String,
+    reachable: bool,
+    latency_ms: Option<u128>,
+    health: Option<String>,
+    #[serde(flatten)]
+    service: ServiceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl StatusCommand {
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+    const HTTP_TIMEOUT: Duration = Duration::from_secs(2);
+    const HEADERS: [&'static str; 10] = [
+        "NAME", "KIND", "ADDRESS", "UP", "LATENCY", "HEALTH", "MODELS", "VRAM", "QUEUE", "UPTIME",
+    ];
+
+    pub fn pattern() -> Regex {
+        Regex::new(r"(?i)^status(?:\s+(--json))?$").unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("StatusCommand::parse(captures: Captures) -> Command");
+        let json = captures.get(1).is_some();
+        debug!("json: {json}");
+        Command::StatusCommand(Self { json })
+    }
+
+    pub async fn exec(&self) -> Result<Option<String>> {
+        trace!("StatusCommand::exec(&self) -> Result<Option<String>>");
+        let config = config::get_config();
+        let mut probes: Vec<LocalBoxFuture<TargetStatus>> = Vec::new();
+        for name in config.servers.keys() {
+            probes.push(probe_server(name).boxed_local());
+        }
+        for name in config.backend_names() {
+            probes.push(probe_backend(name).boxed_local());
+        }
+        let targets = future::join_all(probes).await;
+
+        match self.json {
+            true => Ok(Some(serde_json::to_string_pretty(&targets)?)),
+            false => Ok(Some(table(&targets))),
+        }
+    }
+}
+
+impl TargetStatus {
+    fn new(name: &str, kind: &str, address: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            address: address.to_string(),
+            reachable: false,
+            latency_ms: None,
+            health: None,
+            service: ServiceStatus::default(),
+            error: None,
+        }
+    }
+
+    fn row(&self) -> [String; 10] {
+        fn optional<T: ToString>(value: Option<T>) -> String {
+            value.map_or("-".to_string(), |value| value.to_string())
+        }
+        let vram = match (self.service.vram_used, self.service.vram_total) {
+            (Some(used), Some(total)) => format!("{}/{}", gib(used), gib(total)),
+            (Some(used), None) => gib(used),
+            _ => "-".to_string(),
+        };
+        [
+            self.name.clone(),
+            self.kind.clone(),
+            self.address.clone(),
+            if self.reachable { "yes" } else { "no" }.to_string(),
+            optional(self.latency_ms.map(|latency| format!("{latency} ms"))),
+            optional(self.health.clone()),
+            match self.service.models.is_empty() {
+                true => "-".to_string(),
+                false => self.service.models.join(", "),
+            },
+            vram,
+            optional(self.service.queue),
+            optional(self.service.uptime.map(uptime)),
+        ]
+    }
+}
+
+async fn probe_server(name: &str) -> TargetStatus {
+    trace!("status::probe_server(name: &str) -> TargetStatus");
+    let server = match config::get_config().server(Some(name)) {
+        Ok(server) => server,
+        Err(e) => {
+            let mut status = TargetStatus::new(name, "server", "-");
+            status.error = Some(e.to_string());
+            return status;
+        }
+    };
+    let mut status = TargetStatus::new(name, "server", &server.host);
+    let lifecycle = match Lifecycle::new(&server) {
+        Ok(lifecycle) => lifecycle,
+        Err(e) => {
+            status.error = Some(e.to_string());
+            return status;
+        }
+    };
+
+    let (probe, health, in_flight, service) = tokio::join!(
+        net::probe(
+            &server.host,
+            server.host_port,
+            StatusCommand::CONNECT_TIMEOUT
+        ),
+        lifecycle.health(),
+        lifecycle.in_flight(),
+        lifecycle.service_status()
+    );
+
+    status.reachable = probe.is_open();
+    status.latency_ms = probe.latency().map(|latency| latency.as_millis());
+    if !probe.is_open() {
+        debug!("server {name} probe: {probe}");
+    }
+    status.health = health.map(|health| health.to_string());
+    match service {
+        Some(Ok(service)) => status.service = service,
+        Some(Err(e)) => status.error = Some(e.to_string()),
+        None => {}
+    }
+    if status.service.queue.is_none() {
+        status.service.queue = in_flight;
+    }
+    status
+}
+
+async fn probe_backend(name: String) -> TargetStatus {
+    trace!("status::probe_backend(name: String) -> TargetStatus");
+    let config = config::get_config();
+    let kind = config
+        .backend(Some(&name))
+        .map_or("-".to_string(), |backend| backend.kind);
+    let backend = match slm::backend(Some(&name)) {
+        Ok(backend) => backend,
+        Err(e) => {
+            let mut status = TargetStatus::new(&name, &kind, "-");
+            status.error = Some(e.to_string());
+            return status;
+        }
+    };
+    let mut status = TargetStatus::new(&name, &kind, backend.url());
+
+    let response = async {
+        let client = Client::builder()
+            .timeout(StatusCommand::HTTP_TIMEOUT)
+            .build()?;
+        client.get(backend.url()).send().await
+    };
+    let started = Instant::now();
+    match response.await {
+        Ok(response) => {
+            status.reachable = true;
+            status.latency_ms = Some(started.elapsed().as_millis());
+            status.health = Some(response.status().to_string());
+        }
+        Err(e) => {
+            debug!("backend {name} probe: {e}");
+            return status;
+        }
+    }
+    match backend.status().await {
+        Ok(service) => status.service = service,
+        Err(e) => status.error = Some(e.to_string()),
+    }
+    status
+}
+
+/// Align columns on the widest cell; probe errors are listed below the table.
+fn table(targets: &[TargetStatus]) -> String {
+    let headers = StatusCommand::HEADERS.map(str::to_string);
+    let rows: Vec<[String; 10]> = targets.iter().map(TargetStatus::row).collect();
+    let mut widths = headers.clone().map(|header| header.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut lines = Vec::new();
+    for row in std::iter::once(&headers).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        lines.push(cells.join("  ").trim_end().to_string());
+    }
+    for target in targets {
+        if let Some(error) = &target.error {
+            lines.push(format!("{}: {error}", target.name));
+        }
+    }
+    lines.join("\n")
+}
+
+fn gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+fn uptime(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m"),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::command::status::{gib, uptime};
+
+    #[test]
+    fn format_units() {
+        assert_eq!(uptime(125), "2m");
+        assert_eq!(uptime(2 * 3600 + 60), "2h 1m");
+        assert_eq!(uptime(3 * 86400 + 5 * 3600), "3d 5h");
+        assert_eq!(gib(3 * 1024 * 1024 * 1024 / 2), "1.5 GiB");
+    }
+}
@@ -0,0 +1,133 @@
+use std::rc::Rc;
+
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::command::Command;
+use crate::config;
+use crate::dictionary::{
+    self,
+    render::{self, Labels},
+};
+use crate::error::Result;
+use crate::slm::{SlmBackend, SlmRequest};
+
+/// Synonyms or antonyms of a word, from dictionary definitions that have them and from SLM
+/// otherwise.
+pub struct ThesaurusCommand {
+    relation: Relation,
+    word: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Relation {
+    Synonyms,
+    Antonyms,
+}
+
+impl ThesaurusCommand {
+    const SYNONYMS_SYSTEM: &'static str = "You are a lexicographer. List the synonyms of the \
+    word given by user, in the language of the word, most common first. Respond with one word \
+    or phrase per line, without numbering or any other text.";
+    const ANTONYMS_SYSTEM: &'static str = "You are a lexicographer. List the antonyms of the \
+    word given by user, in the language of the word, most common first. Respond with one word \
+    or phrase per line, without numbering or any other text.";
+
+    pub fn pattern() -> Regex {
+        Regex::new(r"(?i)^(synonyms|antonyms)\s+(?:of\s+|for\s+)?(.*?\S)$").unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("ThesaurusCommand::parse(captures: Captures) -> Command");
+        let relation = match captures[1].to_lowercase().as_str() {
+            "antonyms" => Relation::Antonyms,
+            _ => Relation::Synonyms,
+        };
+        let word = captures[2].to_string();
+        debug!("relation: {relation:?}, word: {word}");
+        Command::ThesaurusCommand(Self { relation, word })
+    }
+
+    pub async fn exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>> {
+        trace!(
+            "ThesaurusCommand::exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>>"
+        );
+        let config = config::get_config().dictionary.clone().unwrap_or_default();
+        let labels = Labels::new(&config);
+        let mut words = Vec::<String>::new();
+        for definition in dictionary::available_entries(&self.word).await {
+            let related = match self.relation {
+                Relation::Synonyms => definition.synonyms,
+                Relation::Antonyms => definition.antonyms,
+            };
+            for word in related {
+                if !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+        if !words.is_empty() {
+            return Ok(Some(self.list(&words, &labels)));
+        }
+
+        let system = match self.relation {
+            Relation::Synonyms => Self::SYNONYMS_SYSTEM,
+            Relation::Antonyms => Self::ANTONYMS_SYSTEM,
+        };
+        let mut request = SlmRequest::new(&self.word);
+        request.set_system(system);
+        let reply = backend.complete(request).await?;
+        debug!("generated {:?}: {reply}", self.relation);
+        let words = reply_words(&reply);
+        if words.is_empty() {
+            return Ok(Some(format!(
+                "# {}\n\n{}",
+                self.heading(&labels),
+                labels.none
+            )));
+        }
+        let response = render::generated(&self.list(&words, &labels), &labels);
+        Ok(Some(response))
+    }
+
+    fn heading(&self, labels: &Labels) -> String {
+        let heading = match self.relation {
+            Relation::Synonyms => &labels.synonyms,
+            Relation::Antonyms => &labels.antonyms,
+        };
+        heading.replace("{word}", &self.word)
+    }
+
+    fn list(&self, words: &[String], labels: &Labels) -> String {
+        let mut lines = vec![format!("# {}", self.heading(labels)), String::new()];
+        lines.extend(words.iter().map(|word| format!("- {word}")));
+        lines.join("\n")
+    }
+}
+
+/// Words listed in SLM reply, one per line, with list markers and numbering removed.
+fn reply_words(reply: &str) -> Vec<String> {
+    let mut words = Vec::<String>::new();
+    for line in reply.lines() {
+        let word = line
+            .trim()
+            .trim_start_matches(|c: char| c.is_ascii_digit() || "-*•.) ".contains(c))
+            .trim_end_matches([',', '.', ';'])
+            .trim();
+        if !word.is_empty() && !word.starts_with("```") && !words.iter().any(|w| w == word) {
+            words.push(word.to_string());
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod test {
+    use crate::command::thesaurus::reply_words;
+
+    #[test]
+    fn words() {
+        let reply = "1. locuință\n2. cămin,\n\n- adăpost\n* locuință\n";
+        assert_eq!(reply_words(reply), ["locuință", "cămin", "adăpost"]);
+    }
+}
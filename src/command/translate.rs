@@ -0,0 +1,116 @@
+use std::rc::Rc;
+
+use lazy_static::lazy_static;
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::command::Command;
+use crate::config;
+use crate::dictionary::{
+    self,
+    render::{self, Labels},
+};
+use crate::error::Result;
+use crate::slm::{SlmBackend, SlmRequest};
+
+/// Translate text to a language, English by default. A single word is looked up in dictionary
+/// translations first; other text, or words without translations, go to SLM.
+pub struct TranslateCommand {
+    text: String,
+}
+
+lazy_static! {
+    /// Text ending with a target language, e.g. `good morning to French`.
+    static ref TARGET: Regex = Regex::new(r"(?i)^(.+?)\s+(?:to|into)\s+(\w+)$").unwrap();
+}
+
+impl TranslateCommand {
+    const LANGUAGE: &'static str = "English";
+    /// Default translation targets, language names and codes separated by whitespace.
+    const LANGUAGES: &'static str = "English en Romanian ro French fr German de Spanish es \
+    Italian it Portuguese pt Russian ru Hungarian hu Ukrainian uk Polish pl Dutch nl Greek el \
+    Turkish tr Chinese zh Japanese ja Korean ko Arabic ar";
+    const TRANSLATOR_SYSTEM: &'static str = "You are a translator. Translate the text given by \
+    user to {language}, keeping its meaning, tone and formatting. Respond with the translation \
+    only, without notes or any other text.";
+
+    pub fn pattern() -> Regex {
+        Regex::new(r"(?i)^translate\s+(.*?\S)$").unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("TranslateCommand::parse(captures: Captures) -> Command");
+        let text = captures[1].to_string();
+        debug!("text: {text}");
+        Command::TranslateCommand(Self { text })
+    }
+
+    pub async fn exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>> {
+        trace!(
+            "TranslateCommand::exec(&self, backend: &Rc<dyn SlmBackend>) -> Result<Option<String>>"
+        );
+        let config = config::get_config().dictionary.clone().unwrap_or_default();
+        let languages: Vec<&str> = match &config.languages {
+            Some(languages) => languages.iter().map(String::as_str).collect(),
+            None => Self::LANGUAGES.split_whitespace().collect(),
+        };
+        let (text, language) = target(&self.text, &languages);
+        let language = language.unwrap_or(Self::LANGUAGE);
+        debug!("text: {text}, language: {language}");
+        if !text.contains(char::is_whitespace) {
+            let mut translations = Vec::<String>::new();
+            for definition in dictionary::available_entries(text).await {
+                for translation in definition.translations {
+                    if translation.language.eq_ignore_ascii_case(language)
+                        && !translations.contains(&translation.text)
+                    {
+                        translations.push(translation.text);
+                    }
+                }
+            }
+            if !translations.is_empty() {
+                return Ok(Some(translations.join(", ")));
+            }
+        }
+
+        let mut request = SlmRequest::new(text);
+        request.set_system(&Self::TRANSLATOR_SYSTEM.replace("{language}", language));
+        let reply = backend.complete(request).await?;
+        Ok(Some(render::generated(&reply, &Labels::new(&config))))
+    }
+}
+
+/// Text to translate and target language, if text ends with `to` or `into` followed by one of
+/// the known languages; otherwise the whole text, e.g. `I want to go to school`.
+fn target<'a>(text: &'a str, languages: &[&str]) -> (&'a str, Option<&'a str>) {
+    if let Some(captures) = TARGET.captures(text) {
+        let language = captures.get(2).unwrap().as_str();
+        if languages
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(language))
+        {
+            return (captures.get(1).unwrap().as_str(), Some(language));
+        }
+    }
+    (text, None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::command::translate::{TranslateCommand, target};
+
+    #[test]
+    fn target_language() {
+        let languages: Vec<&str> = TranslateCommand::LANGUAGES.split_whitespace().collect();
+        assert_eq!(
+            target("I want to go to school", &languages),
+            ("I want to go to school", None)
+        );
+        assert_eq!(
+            target("I want to go to school into French", &languages),
+            ("I want to go to school", Some("French"))
+        );
+        assert_eq!(target("casă to en", &languages), ("casă", Some("en")));
+        assert_eq!(target("walk to", &languages), ("walk to", None));
+    }
+}
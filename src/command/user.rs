@@ -0,0 +1,337 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use lazy_static::lazy_static;
+use log::{debug, trace, warn};
+use regex::{Captures, Regex};
+use reqwest::{Client, Method};
+
+use crate::command::Command;
+use crate::config::{self, CommandConfig, HttpActionConfig};
+use crate::error::{AppError, Result};
+use crate::router::Route;
+
+/// Command declared in config; prompts matching its pattern run its action with the pattern
+/// named captures substituted in action templates.
+#[derive(Clone)]
+pub struct UserCommand {
+    name: String,
+    regex: Regex,
+    action: Action,
+    /// Named captures of matched prompt, empty for captures that did not participate.
+    captures: BTreeMap<String, String>,
+}
+
+#[derive(Clone)]
+enum Action {
+    Http(Method, HttpActionConfig),
+    Shell(String),
+    Prompt {
+        template: String,
+        system: Option<String>,
+        agent: Agent,
+    },
+}
+
+/// Agent answering a prompt action.
+#[derive(Clone)]
+enum Agent {
+    Prompt,
+    Rag(String),
+    Cag(String),
+}
+
+lazy_static! {
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{(\w+)\}").unwrap();
+}
+
+impl UserCommand {
+    const HTTP_TIMEOUT: u64 = 30;
+
+    /// Commands declared in config, by name. Invalid ones, and shell commands unless enabled,
+    /// are skipped with a warning, so that one bad entry does not disable the others.
+    pub fn load() -> Vec<Self> {
+        trace!("UserCommand::load() -> Vec<Self>");
+        let config = config::get_config();
+        let mut commands = Vec::new();
+        for (name, command) in &config.commands {
+            match Self::build(name, command, config.shell_commands) {
+                Ok(command) => commands.push(command),
+                Err(e) => warn!("{e}"),
+            }
+        }
+        debug!("{} user commands loaded", commands.len());
+        commands
+    }
+
+    fn build(name: &str, config: &CommandConfig, shell: bool) -> Result<Self> {
+        trace!(
+            "UserCommand::build(name: &str, config: &CommandConfig, shell: bool) -> Result<Self>"
+        );
+        let invalid = |reason: String| AppError::InvalidConfig(format!("command {name}: {reason}"));
+        let regex =
+            Regex::new(&config.pattern).map_err(|e| invalid(format!("invalid pattern: {e}")))?;
+        let action = match (&config.http, &config.shell, &config.prompt) {
+            (Some(http), None, None) => {
+                let method = http.method.as_deref().unwrap_or("GET").to_uppercase();
+                let method = Method::from_bytes(method.as_bytes())
+                    .map_err(|_| invalid(format!("invalid method {method}")))?;
+                Action::Http(method, http.clone())
+            }
+            (None, Some(_), None) if !shell => {
+                return Err(invalid(
+                    "shell action not enabled, see shell_commands".to_string(),
+                ));
+            }
+            (None, Some(line), None) => Action::Shell(line.clone()),
+            (None, None, Some(prompt)) => {
+                let context = || {
+                    prompt
+                        .context
+                        .clone()
+                        .ok_or_else(|| invalid("agent requires context".to_string()))
+                };
+                let agent = match prompt.agent.as_deref().unwrap_or("prompt") {
+                    "prompt" => Agent::Prompt,
+                    "rag" => Agent::Rag(context()?),
+                    "cag" => Agent::Cag(context()?),
+                    agent => return Err(invalid(format!("unknown agent {agent}"))),
+                };
+                Action::Prompt {
+                    template: prompt.template.clone(),
+                    system: prompt.system.clone(),
+                    agent,
+                }
+            }
+            _ => {
+                return Err(invalid(
+                    "requires exactly one of http, shell or prompt actions".to_string(),
+                ));
+            }
+        };
+        Ok(Self {
+            name: name.to_string(),
+            regex,
+            action,
+            captures: BTreeMap::new(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Route for prompt if it matches command pattern: this command bound to the captures for
+    /// HTTP and shell actions, the expanded prompt template for prompt actions.
+    pub fn route(&self, prompt: &str) -> Option<Route> {
+        trace!("UserCommand::route(&self, prompt: &str) -> Option<Route>");
+        let captures = self.regex.captures(prompt)?;
+        let captures = self.named(&captures);
+        debug!("command: {}, captures: {captures:?}", self.name);
+        if let Action::Prompt {
+            template,
+            system,
+            agent,
+        } = &self.action
+        {
+            return Some(Route::Template {
+                prompt: expand(template, &captures, str::to_string),
+                system: system.clone(),
+                route: Box::new(match agent {
+                    Agent::Prompt => Route::Prompt,
+                    Agent::Rag(context) => Route::Rag(context.clone()),
+                    Agent::Cag(corpus) => Route::Cag(corpus.clone()),
+                }),
+            });
+        }
+        Some(Route::Command(Command::UserCommand(Self {
+            captures,
+            ..self.clone()
+        })))
+    }
+
+    pub async fn exec(&self) -> Result<Option<String>> {
+        trace!("UserCommand::exec(&self) -> Result<Option<String>>");
+        match &self.action {
+            Action::Http(method, http) => self.http(method, http).await.map(Some),
+            Action::Shell(line) => self.shell(line).await.map(Some),
+            // sent to agents instead, see route
+            Action::Prompt { .. } => Ok(None),
+        }
+    }
+
+    async fn http(&self, method: &Method, http: &HttpActionConfig) -> Result<String> {
+        trace!(
+            "UserCommand::http(&self, method: &Method, http: &HttpActionConfig) -> Result<String>"
+        );
+        let url = expand(&http.url, &self.captures, percent_encode);
+        debug!("command: {}, request: {method} {url}", self.name);
+        let timeout = Duration::from_secs(http.timeout.unwrap_or(Self::HTTP_TIMEOUT));
+        let client = Client::builder().timeout(timeout).build()?;
+        let mut request = client.request(method.clone(), &url);
+        let mut json = false;
+        for (name, value) in &http.headers {
+            json |= name.eq_ignore_ascii_case("content-type") && value.contains("json");
+            request = request.header(name, expand(value, &self.captures, str::to_string));
+        }
+        if let Some(body) = &http.body {
+            let body = match json {
+                true => expand(body, &self.captures, json_escape),
+                false => expand(body, &self.captures, str::to_string),
+            };
+            request = request.body(body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(AppError::UserCommand(format!(
+                "{} {method} {url} returned {status}: {}",
+                self.name,
+                text.trim()
+            )));
+        }
+        Ok(text.trim().to_string())
+    }
+
+    /// Run command line with `sh -c`. Captures are passed as positional parameters that the
+    /// placeholders refer to, so that prompt text is never parsed by the shell.
+    async fn shell(&self, line: &str) -> Result<String> {
+        trace!("UserCommand::shell(&self, line: &str) -> Result<String>");
+        let parameters: BTreeMap<String, String> = self
+            .captures
+            .keys()
+            .zip(1..)
+            .map(|(name, index)| (name.clone(), format!("\"${{{index}}}\"")))
+            .collect();
+        let line = expand(line, &parameters, str::to_string);
+        debug!("command: {}, shell: {line}", self.name);
+        let output = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(&line)
+            .arg(&self.name)
+            .args(self.captures.values())
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(AppError::UserCommand(format!(
+                "{} exited with {}: {}",
+                self.name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string())
+    }
+
+    /// Values of all named groups; groups that did not participate in the match are empty so
+    /// that optional parts leave no placeholders behind.
+    fn named(&self, captures: &Captures) -> BTreeMap<String, String> {
+        self.regex
+            .capture_names()
+            .flatten()
+            .map(|name| {
+                let value = captures.name(name).map_or("", |m| m.as_str());
+                (name.to_string(), value.to_string())
+            })
+            .collect()
+    }
+}
+
+/// Replace `{name}` placeholders with escaped capture values in a single pass, so that values
+/// are never expanded again; unknown placeholders are kept as they are.
+fn expand(
+    template: &str,
+    captures: &BTreeMap<String, String>,
+    escape: impl Fn(&str) -> String,
+) -> String {
+    PLACEHOLDER
+        .replace_all(template, |placeholder: &Captures| {
+            match captures.get(&placeholder[1]) {
+                Some(value) => escape(value),
+                None => placeholder[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Percent-encode everything but URL unreserved characters.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Value escaped for use inside a JSON string literal.
+fn json_escape(value: &str) -> String {
+    let literal = serde_json::Value::from(value).to_string();
+    literal[1..literal.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::command::Command;
+    use crate::command::user::{UserCommand, expand, percent_encode};
+    use crate::config::CommandConfig;
+    use crate::router::Route;
+
+    #[test]
+    fn user_command() {
+        let config: CommandConfig = serde_yaml::from_str(
+            r#"
+pattern: '(?i)^weather\s+in\s+(?P<city>.+?)(?:\s+(?P<when>tomorrow))?$'
+http:
+  url: 'https://wttr.in/{city}?format=3&day={when}&{unknown}'
+"#,
+        )
+        .unwrap();
+        let command = UserCommand::build("weather", &config, false).unwrap();
+        assert!(command.route("what is the weather").is_none());
+        let Some(Route::Command(Command::UserCommand(command))) =
+            command.route("Weather in Cluj-Napoca & co")
+        else {
+            panic!("weather prompt not routed to command");
+        };
+        assert_eq!(command.captures["city"], "Cluj-Napoca & co");
+        assert_eq!(command.captures["when"], "");
+        assert_eq!(
+            expand(&config.http.unwrap().url, &command.captures, percent_encode),
+            "https://wttr.in/Cluj-Napoca%20%26%20co?format=3&day=&{unknown}"
+        );
+
+        let config: CommandConfig =
+            serde_yaml::from_str("pattern: '^up (?P<host>\\S+)$'\nshell: ssh {host} uptime")
+                .unwrap();
+        let error = UserCommand::build("up", &config, false).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid config: command up: shell action not enabled, see shell_commands"
+        );
+        assert!(UserCommand::build("up", &config, true).is_ok());
+    }
+
+    #[tokio::test]
+    async fn shell_parameters() {
+        let config: CommandConfig =
+            serde_yaml::from_str("pattern: '^say (?P<text>.+)$'\nshell: echo \"{text}\" {text}")
+                .unwrap();
+        let command = UserCommand::build("say", &config, true).unwrap();
+        let Some(Route::Command(Command::UserCommand(command))) =
+            command.route("say $(echo pwned) 'q'; id")
+        else {
+            panic!("say prompt not routed to command");
+        };
+        assert_eq!(
+            command.exec().await.unwrap().unwrap(),
+            "$(echo pwned) 'q'; id $(echo pwned) 'q'; id"
+        );
+    }
+}
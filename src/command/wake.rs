@@ -0,0 +1,96 @@
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::command::Command;
+use crate::config::{self, AppConfig};
+use crate::error::Result;
+use crate::util::net::{MacAddress, WakeOnLan};
+
+/// Send Wake-on-LAN to a configured server, by name or host, or to a literal MAC address;
+/// unlike server start it does not wait for the machine to come up. Other targets, e.g. `wake
+/// up`, are left to SLM.
+pub struct WakeCommand {
+    target: String,
+}
+
+impl WakeCommand {
+    pub fn pattern() -> Regex {
+        Regex::new(r"(?i)^wake\s+(\S+)$").unwrap()
+    }
+
+    pub fn parse(captures: Captures) -> Command {
+        trace!("WakeCommand::parse(captures: Captures) -> Command");
+        let target = captures[1].to_string();
+        debug!("target: {target}");
+        Command::WakeCommand(Self { target })
+    }
+
+    pub async fn exec(&self) -> Result<Option<String>> {
+        trace!("WakeCommand::exec(&self) -> Result<Option<String>>");
+        let Some((name, wol)) = self.resolve(config::get_config())? else {
+            debug!("{} is neither a server nor a MAC address", self.target);
+            return Ok(None);
+        };
+        let sent = wol.send().await?;
+        let destinations: Vec<String> = sent.iter().map(ToString::to_string).collect();
+        let target = match name {
+            Some(name) => format!("{name} ({})", wol.mac),
+            None => wol.mac.to_string(),
+        };
+        Ok(Some(format!(
+            "Wake-on-LAN sent to {target} via {}",
+            destinations.join(", ")
+        )))
+    }
+
+    /// Server whose name or host matches target, then target parsed as MAC address; none if
+    /// target is neither.
+    fn resolve(&self, config: &AppConfig) -> Result<Option<(Option<String>, WakeOnLan)>> {
+        trace!(
+            "WakeCommand::resolve(&self, config: &AppConfig) -> Result<Option<(Option<String>, WakeOnLan)>>"
+        );
+        if config.servers.contains_key(&self.target) {
+            let server = config.server(Some(&self.target))?;
+            return Ok(Some((Some(server.name), server.wol)));
+        }
+        for name in config.servers.keys() {
+            if let Ok(server) = config.server(Some(name))
+                && server.host.eq_ignore_ascii_case(&self.target)
+            {
+                return Ok(Some((Some(server.name), server.wol)));
+            }
+        }
+        Ok(self
+            .target
+            .parse::<MacAddress>()
+            .ok()
+            .map(|mac| (None, WakeOnLan::new(mac))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::command::Command;
+    use crate::config::AppConfig;
+
+    #[test]
+    fn resolve() {
+        let yaml = "servers:\n  box:\n    host: gpu.local\n    mac: 10-7c-61-5f-10-be\n";
+        let config: AppConfig = serde_yaml::from_str(yaml).unwrap();
+        let resolve = |prompt: &str| match prompt.parse::<Command>() {
+            Ok(Command::WakeCommand(command)) => command.resolve(&config).unwrap(),
+            _ => panic!("{prompt} not parsed as wake command"),
+        };
+
+        for prompt in ["wake box", "wake GPU.local"] {
+            let (name, wol) = resolve(prompt).unwrap();
+            assert_eq!(name.as_deref(), Some("box"));
+            assert_eq!(wol.mac.to_string(), "10:7c:61:5f:10:be");
+        }
+        let (name, wol) = resolve("wake 10:7c:61:5f:10:bf").unwrap();
+        assert!(name.is_none());
+        assert_eq!(wol.mac.to_string(), "10:7c:61:5f:10:bf");
+        assert!(resolve("wake up").is_none());
+        assert!(resolve("wake everyone").is_none());
+    }
+}
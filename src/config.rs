@@ -1,50 +1,606 @@
-use std::{fs, sync::OnceLock};
+use std::{collections::BTreeMap, fs, sync::OnceLock, time::Duration};
 
-use log::trace;
-use serde::Deserialize;
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
 
-use crate::error::{AppError, Result};
+use crate::{
+    error::{AppError, Result},
+    util::net::{Broadcast, MacAddress, WakeOnLan},
+};
 
 pub static CONFIG: OnceLock<AppConfig> = OnceLock::new();
+static CONFIG_PATH: OnceLock<String> = OnceLock::new();
 
 pub fn init_config(path: &str) -> Result<()> {
     trace!("config::init_config(path: &str) -> Result<()>");
-    let config = AppConfig::load(path);
-    CONFIG.set(config).map_err(|_| AppError::ConfigError)
+    let config = AppConfig::load(path)?;
+    CONFIG.set(config).map_err(|_| AppError::ConfigError)?;
+    CONFIG_PATH
+        .set(path.to_string())
+        .map_err(|_| AppError::ConfigError)
+}
+
+/// Initialize config from the fixture checked in for unit tests, so that results do not depend
+/// on the local config; all tests must use it since config is initialized once per process.
+#[cfg(test)]
+pub fn init_test_config() {
+    let _ = init_config(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/config.yml"
+    ));
+}
+
+/// Path config was loaded from, passed on to spawned processes.
+pub fn get_config_path() -> &'static str {
+    CONFIG_PATH.get().expect("Config not initialized")
 }
 
 pub fn get_config() -> &'static AppConfig {
     CONFIG.get().expect("Config not initialized")
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct AppConfig {
     pub slm_url: Option<String>,
+    pub slm_backend: Option<String>,
+    pub slm_model: Option<String>,
+    pub slm_api_key: Option<String>,
+    pub ollama: Option<OllamaConfig>,
+    #[serde(default)]
+    pub backends: BTreeMap<String, BackendConfig>,
     pub rag_system: Option<String>,
+    pub rag_top_k: Option<usize>,
+    pub cag_system: Option<String>,
+    /// Named document sets for cache-augmented generation, each a list of files or directories.
+    #[serde(default)]
+    pub corpora: BTreeMap<String, Vec<String>>,
     pub prompt_system: Option<String>,
     pub user_profile: Option<String>,
     pub system_settings: Option<String>,
+    pub attachment_limit: Option<usize>,
+    pub max_tool_iterations: Option<usize>,
+    pub router: Option<RouterConfig>,
+    /// Machines hosting SLM services, managed by `server` commands.
+    #[serde(default)]
+    pub servers: BTreeMap<String, ServerConfig>,
+    pub dictionary: Option<DictionaryConfig>,
+    /// Commands declared by user, by name, matched after the built-in ones.
+    #[serde(default)]
+    pub commands: BTreeMap<String, CommandConfig>,
+    /// Allow user commands with shell action; off by default since prompt text ends up on a
+    /// shell command line.
+    #[serde(default)]
+    pub shell_commands: bool,
+}
+
+/// Router settings; SLM classification is used only when enabled, optionally with a dedicated,
+/// cheaper backend.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RouterConfig {
+    #[serde(default)]
+    pub classify: bool,
+    pub backend: Option<String>,
+}
+
+/// Dictionary definitions source: `store` selects a MongoDB database, the default, or a local
+/// JSON lines `file`. For MongoDB, credentials and TLS given here override the ones from URI.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct DictionaryConfig {
+    pub store: Option<String>,
+    /// File store path, default to `definitions.jsonl` in data directory.
+    pub path: Option<String>,
+    pub uri: Option<String>,
+    pub database: Option<String>,
+    pub collection: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Database holding user credentials, default to `admin`.
+    pub auth_source: Option<String>,
+    /// Connect over TLS, with the given options, when present.
+    pub tls: Option<TlsConfig>,
+    /// Time to find a reachable server before giving up, in seconds.
+    pub timeout: Option<u64>,
+    pub labels: Option<DictionaryLabels>,
+    /// System prompt for definitions generated by SLM when the word is not in dictionary.
+    pub system: Option<String>,
+    /// Language names and codes accepted as translation targets, default to common ones.
+    pub languages: Option<Vec<String>>,
+}
+
+/// Dictionary output labels, default to Romanian.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct DictionaryLabels {
+    pub expressions: Option<String>,
+    /// Notice shown above definitions and answers generated by SLM.
+    pub generated: Option<String>,
+    pub language: Option<String>,
+    /// Note on definitions left out by limit, with `{count}` placeholder.
+    pub more: Option<String>,
+    /// Did you mean line, with `{word}` and `{words}` placeholders.
+    pub suggestions: Option<String>,
+    /// Thesaurus headings, with `{word}` placeholder.
+    pub synonyms: Option<String>,
+    pub antonyms: Option<String>,
+    /// Shown when there are no synonyms or antonyms.
+    pub none: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct TlsConfig {
+    pub ca_file: Option<String>,
+    /// Client certificate and private key, in one PEM file.
+    pub cert_key_file: Option<String>,
+    #[serde(default)]
+    pub allow_invalid_certificates: bool,
+}
+
+impl DictionaryConfig {
+    const STORE: &'static str = "mongo";
+    const URI: &'static str = "mongodb://localhost:27017";
+    const DATABASE: &'static str = "kb";
+    const COLLECTION: &'static str = "data";
+    const TIMEOUT: u64 = 5;
+
+    pub fn store(&self) -> &str {
+        self.store.as_deref().unwrap_or(Self::STORE)
+    }
+
+    pub fn uri(&self) -> &str {
+        self.uri.as_deref().unwrap_or(Self::URI)
+    }
+
+    pub fn database(&self) -> &str {
+        self.database.as_deref().unwrap_or(Self::DATABASE)
+    }
+
+    pub fn collection(&self) -> &str {
+        self.collection.as_deref().unwrap_or(Self::COLLECTION)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(Self::TIMEOUT))
+    }
+}
+
+/// User command: prompts matching `pattern` run its action, one of `http`, `shell` or `prompt`,
+/// with `{name}` placeholders in action templates replaced by pattern named captures.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandConfig {
+    pub pattern: String,
+    pub http: Option<HttpActionConfig>,
+    /// Command line run with `sh -c`, with captures passed as positional parameters; requires
+    /// `shell_commands`.
+    pub shell: Option<String>,
+    pub prompt: Option<PromptActionConfig>,
+}
+
+/// HTTP request template; captures are percent-encoded in URL and JSON escaped in body if
+/// content type is JSON. Response body is the command response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpActionConfig {
+    /// Request method, default to `GET`.
+    pub method: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+    /// Request timeout, in seconds.
+    pub timeout: Option<u64>,
+}
+
+/// Prompt template sent with given system role to an agent: `prompt`, the default, `rag` over
+/// `context` index or `cag` over `context` corpus.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptActionConfig {
+    pub template: String,
+    pub system: Option<String>,
+    pub agent: Option<String>,
+    pub context: Option<String>,
+}
+
+/// Named SLM backend; `kind` selects the implementation from the backends registry, that is,
+/// `jarvis`, `openai` or `ollama`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackendConfig {
+    pub kind: String,
+    pub url: Option<String>,
+    pub model: Option<String>,
+    pub embedding_model: Option<String>,
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub default: bool,
+    /// Advertise tools to the model; enable only if backend and model support function calling.
+    #[serde(default)]
+    pub tools: bool,
+    /// Server hosting the backend, started automatically when a prompt finds it not ready.
+    pub server: Option<String>,
+    pub ollama: Option<OllamaConfig>,
+}
+
+/// Named machine as written in config; required fields are checked when resolved to [Server],
+/// so that one incomplete entry does not invalidate the whole configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    pub host: Option<String>,
+    pub mac: Option<String>,
+    /// Wake-on-LAN broadcast address, e.g. directed broadcast of the server subnet; default to
+    /// limited broadcast plus directed broadcast of every local interface.
+    pub broadcast: Option<String>,
+    /// Local interface whose directed broadcast is used for Wake-on-LAN.
+    pub interface: Option<String>,
+    /// SecureOn password, six bytes in MAC address notation.
+    pub secure_on: Option<String>,
+    /// Wake-on-LAN destination ports, default to both discard (9) and echo (7).
+    pub wol_ports: Option<Vec<u16>>,
+    /// Times each Wake-on-LAN packet is sent.
+    pub wol_repeat: Option<u32>,
+    /// Port probed to check that the machine is up, default to SSH.
+    pub host_port: Option<u16>,
+    /// Port probed to check that the SLM service is up.
+    pub service_port: Option<u16>,
+    /// HTTP endpoint answering 2xx once the model is loaded, e.g. llama.cpp `/health` that
+    /// responds 503 while loading.
+    pub health_url: Option<String>,
+    /// Overall deadline for the start sequence, in seconds.
+    pub wake_timeout: Option<u64>,
+    /// Interval between Wake-on-LAN packets while the host is not up, in seconds.
+    pub wol_interval: Option<u64>,
+    pub shutdown_url: Option<String>,
+    /// Bearer token authenticating HTTP shutdown and management requests.
+    pub api_token: Option<String>,
+    /// Shut down by running a command over SSH instead of the HTTP shutdown endpoint.
+    pub ssh: Option<SshConfig>,
+    /// Deadline for the host to go down after shutdown was requested, in seconds.
+    pub stop_timeout: Option<u64>,
+    /// llama.cpp `/slots` endpoint used to report in-flight requests before shutdown.
+    pub slots_url: Option<String>,
+    /// Endpoint returning service status as JSON with optional `models`, `vram_used`,
+    /// `vram_total`, `queue` and `uptime` fields, e.g. a script exposing GPU usage.
+    pub status_url: Option<String>,
+    /// Shut down after this many minutes without requests; disabled if missing.
+    pub idle_sleep: Option<u64>,
+    pub history_url: Option<String>,
+    #[serde(default)]
+    pub default: bool,
+}
+
+/// SSH access for shutdown; key is optional when the agent or default identity is used.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SshConfig {
+    pub user: Option<String>,
+    pub key: Option<String>,
+    pub port: Option<u16>,
+    pub command: Option<String>,
+}
+
+/// Server configuration validated and completed with defaults.
+#[derive(Debug, Clone)]
+pub struct Server {
+    pub name: String,
+    pub host: String,
+    pub wol: WakeOnLan,
+    pub host_port: u16,
+    pub service_port: u16,
+    pub health_url: String,
+    pub wake_timeout: Duration,
+    pub wol_interval: Duration,
+    /// Authenticated shutdown method, none if neither API token nor SSH is configured.
+    pub shutdown: Option<Shutdown>,
+    pub stop_timeout: Duration,
+    pub slots_url: Option<String>,
+    pub status_url: Option<String>,
+    pub idle_sleep: Option<Duration>,
+    pub api_token: Option<String>,
+    pub history_url: String,
+}
+
+impl Server {
+    /// Configured shutdown method, failing if there is no authenticated one.
+    pub fn shutdown(&self) -> Result<&Shutdown> {
+        self.shutdown.as_ref().ok_or_else(|| {
+            AppError::InvalidConfig(format!(
+                "server {}: shutdown requires api_token or ssh",
+                self.name
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Shutdown {
+    Http {
+        url: String,
+        token: String,
+    },
+    Ssh {
+        destination: String,
+        key: Option<String>,
+        port: u16,
+        command: String,
+    },
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct OllamaConfig {
+    pub api: Option<OllamaApi>,
+    pub temperature: Option<f32>,
+    pub num_ctx: Option<u32>,
+    pub keep_alive: Option<KeepAlive>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OllamaApi {
+    #[default]
+    Chat,
+    /// Single turn completion; conversation history is not sent on this API.
+    Generate,
+}
+
+/// How long Ollama keeps the model loaded after a request: seconds, with negative values meaning
+/// forever, or a duration string like `10m`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeepAlive {
+    Seconds(i64),
+    Duration(String),
 }
 
 impl AppConfig {
-    pub fn load(path: &str) -> Self {
-        if let Ok(yaml) = fs::read_to_string(&path) {
-            if let Ok(config) = serde_yaml::from_str(&yaml) {
-                return config;
+    const LEGACY_BACKEND: &'static str = "default";
+
+    /// Load config from YAML file; a missing file yields the default config, while a file that
+    /// cannot be read or parsed is an error naming the path.
+    pub fn load(path: &str) -> Result<Self> {
+        trace!("AppConfig::load(path: &str) -> Result<Self>");
+        let yaml = match fs::read_to_string(path) {
+            Ok(yaml) => yaml,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("config file {path} not found, using defaults");
+                return Ok(AppConfig::default());
             }
+            Err(e) => return Err(AppError::InvalidConfig(format!("{path}: {e}"))),
+        };
+        serde_yaml::from_str(&yaml).map_err(|e| AppError::InvalidConfig(format!("{path}: {e}")))
+    }
+
+    /// Resolve backend configuration by name or, if name is missing, the one marked as default.
+    /// When there is no `backends` section, legacy `slm_*` properties define the single backend.
+    pub fn backend(&self, name: Option<&str>) -> Result<BackendConfig> {
+        trace!("AppConfig::backend(&self, name: Option<&str>) -> Result<BackendConfig>");
+        if self.backends.is_empty() {
+            return match name {
+                None | Some(Self::LEGACY_BACKEND) => Ok(self.legacy_backend()),
+                Some(name) => Err(AppError::UnknownBackend(name.to_string())),
+            };
+        }
+
+        if let Some(name) = name {
+            return self
+                .backends
+                .get(name)
+                .cloned()
+                .ok_or_else(|| AppError::UnknownBackend(name.to_string()));
+        }
+        let defaults: Vec<&BackendConfig> = self
+            .backends
+            .values()
+            .filter(|backend| backend.default)
+            .collect();
+        match (defaults.as_slice(), self.backends.len()) {
+            ([backend], _) => Ok((*backend).clone()),
+            ([], 1) => Ok(self.backends.values().next().unwrap().clone()),
+            _ => Err(AppError::InvalidConfig(
+                "exactly one backend must be marked as default".to_string(),
+            )),
         }
-        AppConfig::default()
     }
-}
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            slm_url: None,
-            rag_system: None,
-            prompt_system: None,
-            user_profile: None,
-            system_settings: None,
+    /// Names of configured backends, the legacy one if there is no `backends` section.
+    pub fn backend_names(&self) -> Vec<String> {
+        match self.backends.is_empty() {
+            true => vec![Self::LEGACY_BACKEND.to_string()],
+            false => self.backends.keys().cloned().collect(),
+        }
+    }
+
+    /// Resolve server by name or, if name is missing, the one marked as default or the only one.
+    pub fn server(&self, name: Option<&str>) -> Result<Server> {
+        trace!("AppConfig::server(&self, name: Option<&str>) -> Result<Server>");
+        let (name, server) = match name {
+            Some(name) => self
+                .servers
+                .get_key_value(name)
+                .ok_or_else(|| AppError::InvalidConfig(format!("unknown server {name}")))?,
+            None => {
+                let defaults: Vec<(&String, &ServerConfig)> = self
+                    .servers
+                    .iter()
+                    .filter(|(_, server)| server.default)
+                    .collect();
+                match (defaults.as_slice(), self.servers.len()) {
+                    ([server], _) => *server,
+                    ([], 1) => self.servers.iter().next().unwrap(),
+                    ([], 0) => {
+                        return Err(AppError::InvalidConfig("no servers configured".to_string()));
+                    }
+                    _ => {
+                        return Err(AppError::InvalidConfig(
+                            "exactly one server must be marked as default".to_string(),
+                        ));
+                    }
+                }
+            }
+        };
+        server.resolve(name)
+    }
+
+    fn legacy_backend(&self) -> BackendConfig {
+        BackendConfig {
+            kind: self.slm_backend.clone().unwrap_or("jarvis".to_string()),
+            url: self.slm_url.clone(),
+            model: self.slm_model.clone(),
+            embedding_model: None,
+            api_key: self.slm_api_key.clone(),
+            default: true,
+            tools: false,
+            server: None,
+            ollama: self.ollama.clone(),
         }
     }
 }
+
+impl ServerConfig {
+    const HOST_PORT: u16 = 22;
+    const SERVICE_PORT: u16 = 1964;
+    const WAKE_TIMEOUT: u64 = 300;
+    const WOL_INTERVAL: u64 = 15;
+    const STOP_TIMEOUT: u64 = 60;
+    const SSH_PORT: u16 = 22;
+    const SSH_COMMAND: &'static str = "sudo systemctl suspend";
+
+    fn resolve(&self, name: &str) -> Result<Server> {
+        let required = |field: &Option<String>, label: &str| {
+            field
+                .clone()
+                .filter(|value| !value.trim().is_empty())
+                .ok_or_else(|| AppError::InvalidConfig(format!("server {name}: missing {label}")))
+        };
+        let host = required(&self.host, "host")?;
+        let invalid = |label: &str, e: AppError| {
+            AppError::InvalidConfig(format!("server {name}: invalid {label}: {e}"))
+        };
+        let mac: MacAddress = required(&self.mac, "mac")?
+            .parse()
+            .map_err(|e| invalid("mac", e))?;
+        let broadcast = match (&self.broadcast, &self.interface) {
+            (Some(address), _) => Broadcast::Address(address.trim().parse().map_err(|_| {
+                AppError::InvalidConfig(format!("server {name}: invalid broadcast {address}"))
+            })?),
+            (None, Some(interface)) => Broadcast::Interface(interface.clone()),
+            (None, None) => Broadcast::Local,
+        };
+        let wol = WakeOnLan {
+            mac,
+            password: match &self.secure_on {
+                Some(password) => Some(password.parse().map_err(|e| invalid("secure_on", e))?),
+                None => None,
+            },
+            broadcast,
+            ports: self
+                .wol_ports
+                .clone()
+                .filter(|ports| !ports.is_empty())
+                .unwrap_or_else(|| WakeOnLan::PORTS.to_vec()),
+            repeat: self.wol_repeat.unwrap_or(WakeOnLan::REPEAT),
+        };
+        let service_port = self.service_port.unwrap_or(Self::SERVICE_PORT);
+        let shutdown = match (&self.ssh, &self.api_token) {
+            (Some(ssh), _) => Some(Shutdown::Ssh {
+                destination: match &ssh.user {
+                    Some(user) => format!("{user}@{host}"),
+                    None => host.clone(),
+                },
+                key: ssh.key.clone(),
+                port: ssh.port.unwrap_or(Self::SSH_PORT),
+                command: ssh
+                    .command
+                    .as_deref()
+                    .unwrap_or(Self::SSH_COMMAND)
+                    .to_string(),
+            }),
+            (None, Some(token)) => Some(Shutdown::Http {
+                url: self
+                    .shutdown_url
+                    .clone()
+                    .unwrap_or_else(|| format!("http://{host}:{service_port}/shutdown")),
+                token: token.clone(),
+            }),
+            (None, None) => None,
+        };
+        Ok(Server {
+            name: name.to_string(),
+            wol,
+            host_port: self.host_port.unwrap_or(Self::HOST_PORT),
+            service_port,
+            health_url: self
+                .health_url
+                .clone()
+                .unwrap_or_else(|| format!("http://{host}:{service_port}/health")),
+            wake_timeout: Duration::from_secs(self.wake_timeout.unwrap_or(Self::WAKE_TIMEOUT)),
+            wol_interval: Duration::from_secs(self.wol_interval.unwrap_or(Self::WOL_INTERVAL)),
+            shutdown,
+            stop_timeout: Duration::from_secs(self.stop_timeout.unwrap_or(Self::STOP_TIMEOUT)),
+            slots_url: self.slots_url.clone(),
+            status_url: self.status_url.clone(),
+            idle_sleep: self
+                .idle_sleep
+                .map(|minutes| Duration::from_secs(minutes * 60)),
+            api_token: self.api_token.clone(),
+            history_url: self
+                .history_url
+                .clone()
+                .unwrap_or_else(|| format!("http://{host}:{service_port}/history/clear")),
+            host,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+
+    use crate::{config::AppConfig, util::net::Broadcast};
+
+    #[test]
+    fn load() {
+        let path = env::temp_dir().join(format!("jarvis-config-{}.yml", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        assert!(AppConfig::load(path).unwrap().servers.is_empty());
+
+        fs::write(path, "servers: [broken\n").unwrap();
+        let error = AppConfig::load(path).unwrap_err().to_string();
+        fs::remove_file(path).unwrap();
+        assert!(error.starts_with(&format!("Invalid config: {path}: ")));
+    }
+
+    #[test]
+    fn server() {
+        let yaml = "
+servers:
+  gpu-box:
+    host: 192.168.0.5
+    mac: 10-7c-61-5f-10-be
+    default: true
+  laptop:
+    host: 192.168.0.7
+";
+        let config: AppConfig = serde_yaml::from_str(yaml).unwrap();
+        let server = config.server(None).unwrap();
+        assert_eq!(server.name, "gpu-box");
+        assert_eq!(server.wol.broadcast, Broadcast::Local);
+        assert_eq!(server.wol.mac.to_string(), "10:7c:61:5f:10:be");
+        assert!(server.shutdown.is_none());
+
+        let error = config.server(Some("laptop")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid config: server laptop: missing mac"
+        );
+        assert!(config.server(Some("nas")).is_err());
+
+        let yaml = "
+servers:
+  nas:
+    host: 192.168.0.9
+    mac: 10-7c-61-5f-10
+";
+        let config: AppConfig = serde_yaml::from_str(yaml).unwrap();
+        let error = config.server(None).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid config: server nas: invalid mac: Invalid address: 10-7c-61-5f-10: expected 6 bytes"
+        );
+    }
+}
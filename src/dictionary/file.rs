@@ -0,0 +1,180 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use async_trait::async_trait;
+use log::{debug, trace};
+
+use crate::{
+    config::DictionaryConfig,
+    dictionary::{self, Definition, DictionaryStore, Match},
+    error::Result,
+    util::dirs,
+};
+
+/// Definitions kept in a local JSON lines file, one definition per line, for use without a
+/// database server; the file is read on every search.
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    const DICTIONARY_DIR: &'static str = "dictionary";
+    const NAME: &'static str = "definitions";
+    const EXTENSION: &'static str = "jsonl";
+
+    pub fn build(config: &DictionaryConfig) -> Result<Box<dyn DictionaryStore>> {
+        trace!("FileStore::build(config: &DictionaryConfig) -> Result<Box<dyn DictionaryStore>>");
+        let path = match &config.path {
+            Some(path) => PathBuf::from(path),
+            None => dirs::data_file(Self::DICTIONARY_DIR, Self::NAME, Self::EXTENSION)?,
+        };
+        debug!("dictionary file: {}", path.display());
+        Ok(Box::new(Self { path }))
+    }
+
+    /// Write all definitions to a sibling file first, then rename it over the store file, so
+    /// that a failure leaves the original in place.
+    fn rewrite(&self, definitions: &[Definition]) -> Result<()> {
+        trace!("FileStore::rewrite(&self, definitions: &[Definition]) -> Result<()>");
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut lines = String::new();
+        for definition in definitions {
+            lines.push_str(&serde_json::to_string(definition)?);
+            lines.push('\n');
+        }
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, lines)?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Vec<Definition>> {
+        trace!("FileStore::load(&self) -> Result<Vec<Definition>>");
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        dictionary::read_dump(&self.path)
+    }
+}
+
+#[async_trait(?Send)]
+impl DictionaryStore for FileStore {
+    async fn search(&self, word: &str) -> Result<Vec<Definition>> {
+        trace!("FileStore::search(&self, word: &str) -> Result<Vec<Definition>>");
+        let definitions = self
+            .load()?
+            .into_iter()
+            .filter(|definition| definition.matches(word) < Match::Text)
+            .collect();
+        Ok(definitions)
+    }
+
+    async fn headwords(&self) -> Result<Vec<String>> {
+        trace!("FileStore::headwords(&self) -> Result<Vec<String>>");
+        let mut headwords: Vec<String> = self
+            .load()?
+            .into_iter()
+            .map(|definition| definition.word)
+            .collect();
+        headwords.sort();
+        headwords.dedup();
+        Ok(headwords)
+    }
+
+    async fn lookup(&self, words: &[String]) -> Result<Vec<Definition>> {
+        trace!("FileStore::lookup(&self, words: &[String]) -> Result<Vec<Definition>>");
+        let definitions = self
+            .load()?
+            .into_iter()
+            .filter(|definition| words.contains(&definition.word))
+            .collect();
+        Ok(definitions)
+    }
+
+    async fn import(&self, definitions: Vec<Definition>) -> Result<usize> {
+        trace!("FileStore::import(&self, definitions: Vec<Definition>) -> Result<usize>");
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        for definition in &definitions {
+            writeln!(file, "{}", serde_json::to_string(definition)?)?;
+        }
+        Ok(definitions.len())
+    }
+
+    /// Rewrite the file without definitions of given headword, see [FileStore::rewrite].
+    async fn remove(&self, word: &str) -> Result<usize> {
+        trace!("FileStore::remove(&self, word: &str) -> Result<usize>");
+        let (removed, kept): (Vec<Definition>, Vec<Definition>) = self
+            .load()?
+            .into_iter()
+            .partition(|definition| definition.word == word);
+        if removed.is_empty() {
+            return Ok(0);
+        }
+        self.rewrite(&kept)?;
+        Ok(removed.len())
+    }
+
+    /// Rewrite the file with definitions of given headword replaced, in a single rename.
+    async fn replace(&self, word: &str, definitions: Vec<Definition>) -> Result<usize> {
+        trace!(
+            "FileStore::replace(&self, word: &str, definitions: Vec<Definition>) -> Result<usize>"
+        );
+        let mut kept: Vec<Definition> = self
+            .load()?
+            .into_iter()
+            .filter(|definition| definition.word != word)
+            .collect();
+        let count = definitions.len();
+        kept.extend(definitions);
+        self.rewrite(&kept)?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use crate::dictionary::{DictionaryStore, file::FileStore, read_dump};
+
+    #[tokio::test]
+    async fn import_search() {
+        let dump = env::temp_dir().join(format!("jarvis-dump-{}.json", process::id()));
+        fs::write(
+            &dump,
+            r#"{"_id": {"$oid": "1"}, "word": "Casă", "key": "casa", "part_of_speech": "Substantiv", "meanings": [{"definition": "Clădire de locuit.", "examples": []}], "expressions": [{"phrase": "casa de bani", "definition": "seif"}]}
+{"word": "bani", "key": "bani", "part_of_speech": null, "meanings": [], "expressions": []}"#,
+        )
+        .unwrap();
+        let path = env::temp_dir().join(format!("jarvis-store-{}.jsonl", process::id()));
+        let store = FileStore { path: path.clone() };
+        assert_eq!(store.import(read_dump(&dump).unwrap()).await.unwrap(), 2);
+
+        assert_eq!(store.search("CASA").await.unwrap()[0].word, "Casă");
+        assert_eq!(store.search("bani").await.unwrap().len(), 2);
+        assert!(store.search("masă").await.unwrap().is_empty());
+        assert_eq!(store.headwords().await.unwrap(), ["Casă", "bani"]);
+        assert_eq!(store.lookup(&["bani".to_string()]).await.unwrap().len(), 1);
+        let mut edited = store.lookup(&["bani".to_string()]).await.unwrap();
+        edited[0].part_of_speech = Some("Substantiv".to_string());
+        edited.push(edited[0].clone());
+        assert_eq!(store.replace("bani", edited.clone()).await.unwrap(), 2);
+        assert_eq!(store.lookup(&["bani".to_string()]).await.unwrap(), edited);
+        assert_eq!(store.search("casa").await.unwrap().len(), 1);
+        assert_eq!(store.remove("bani").await.unwrap(), 2);
+        assert_eq!(store.headwords().await.unwrap(), ["Casă"]);
+        fs::remove_file(dump).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+}
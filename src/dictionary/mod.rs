@@ -0,0 +1,452 @@
+mod file;
+mod mongo;
+pub mod render;
+
+use std::{fs, path::Path, rc::Rc};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use log::{debug, trace, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, DictionaryConfig},
+    error::{AppError, Result},
+};
+
+/// Dictionary entry; `key` is the headword without diacritics and, like the lists, may be left
+/// out when an entry is written by hand, as may the word when given on command line.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Definition {
+    #[serde(default)]
+    pub word: String,
+    #[serde(default)]
+    pub key: String,
+    pub part_of_speech: Option<String>,
+    #[serde(default)]
+    pub meanings: Vec<Meaning>,
+    #[serde(default)]
+    pub expressions: Vec<Expression>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synonyms: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub antonyms: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub translations: Vec<Translation>,
+    /// Inflected forms, e.g. verb conjugation, in the order they are shown.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forms: Vec<Form>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub phrase: String,
+    pub definition: String,
+}
+
+/// Headword translation; language is given as written in dictionary, name or code.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Translation {
+    pub language: String,
+    pub text: String,
+}
+
+/// Inflected form with its grammatical label, e.g. `prezent, pers. 1 sg.`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Form {
+    pub label: String,
+    pub form: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Example {
+    pub text: String,
+    pub source: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Meaning {
+    pub definition: String,
+    #[serde(default)]
+    pub examples: Vec<Example>,
+}
+
+/// Result of a dictionary lookup: definitions found, best first, how many more matched but
+/// were left out by limit, and headwords close to word when nothing matched. Definitions are
+/// `generated` when written by SLM instead of coming from the store.
+#[derive(Serialize, Debug)]
+pub struct Lookup {
+    pub word: String,
+    pub definitions: Vec<Definition>,
+    pub more: usize,
+    pub suggestions: Vec<String>,
+    pub generated: bool,
+}
+
+/// How a definition matches the looked up word, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Match {
+    /// Headword equal to word, ignoring case.
+    Exact,
+    /// Headword or key equal to word ignoring case and diacritics, e.g. `tara` for `țară`.
+    Folded,
+    /// Word is part of one of the definition expressions.
+    Expression,
+    /// Found by store full text search only, e.g. word used in a definition.
+    Text,
+}
+
+/// Storage of dictionary definitions, searched by word and loaded from dumps.
+#[async_trait(?Send)]
+pub trait DictionaryStore {
+    /// Candidate definitions for given word, in no particular order: definitions of the word
+    /// and definitions with expressions containing it, ignoring case and diacritics.
+    async fn search(&self, word: &str) -> Result<Vec<Definition>>;
+
+    /// All headwords, for prefix and fuzzy matching.
+    async fn headwords(&self) -> Result<Vec<String>>;
+
+    /// Definitions of given headwords.
+    async fn lookup(&self, words: &[String]) -> Result<Vec<Definition>>;
+
+    /// Append definitions and return how many were stored.
+    async fn import(&self, definitions: Vec<Definition>) -> Result<usize>;
+
+    /// Remove all definitions of given headword and return how many were removed.
+    async fn remove(&self, word: &str) -> Result<usize>;
+
+    /// Replace all definitions of given headword with given ones and return how many were
+    /// stored. A failure never loses the stored definitions.
+    async fn replace(&self, word: &str, definitions: Vec<Definition>) -> Result<usize>;
+}
+
+pub type StoreBuilder = fn(&DictionaryConfig) -> Result<Box<dyn DictionaryStore>>;
+
+lazy_static! {
+    static ref STORES: Vec<(&'static str, StoreBuilder)> = vec![
+        ("mongo", mongo::MongoStore::build),
+        ("file", file::FileStore::build),
+    ];
+}
+
+/// Create the dictionary store selected in config, MongoDB if dictionary section is missing.
+pub fn store() -> Result<Rc<dyn DictionaryStore>> {
+    trace!("dictionary::store() -> Result<Rc<dyn DictionaryStore>>");
+    let config = config::get_config().dictionary.clone().unwrap_or_default();
+    for (kind, builder) in STORES.iter() {
+        if *kind == config.store() {
+            return Ok(Rc::from(builder(&config)?));
+        }
+    }
+    Err(AppError::InvalidConfig(format!(
+        "unknown dictionary store {}",
+        config.store()
+    )))
+}
+
+/// Read definitions from a dump file: a JSON array or JSON lines, e.g. `mongoexport` output
+/// whose `_id` fields are ignored.
+pub fn read_dump(path: &Path) -> Result<Vec<Definition>> {
+    trace!("dictionary::read_dump(path: &Path) -> Result<Vec<Definition>>");
+    let text = fs::read_to_string(path)?;
+    let invalid = |line: usize, e: serde_json::Error| {
+        AppError::Dictionary(format!("{}:{line}: {e}", path.display()))
+    };
+    if text.trim_start().starts_with('[') {
+        return serde_json::from_str(&text).map_err(|e| invalid(e.line(), e));
+    }
+    let mut definitions = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if !line.trim().is_empty() {
+            definitions.push(serde_json::from_str(line).map_err(|e| invalid(index + 1, e))?);
+        }
+    }
+    debug!(
+        "{} definitions read from {}",
+        definitions.len(),
+        path.display()
+    );
+    Ok(definitions)
+}
+
+impl Definition {
+    /// Classify how this definition matches the looked up word.
+    pub fn matches(&self, word: &str) -> Match {
+        let word = word.trim();
+        if self.word.to_lowercase() == word.to_lowercase() {
+            return Match::Exact;
+        }
+        let word = fold(word);
+        if fold(&self.word) == word || fold(&self.key) == word {
+            return Match::Folded;
+        }
+        let tokens: Vec<&str> = word.split_whitespace().collect();
+        let contains = |phrase: &str| {
+            let phrase = fold(phrase);
+            let phrase: Vec<&str> = phrase
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|token| !token.is_empty())
+                .collect();
+            !tokens.is_empty() && phrase.windows(tokens.len()).any(|window| window == tokens)
+        };
+        if self
+            .expressions
+            .iter()
+            .any(|expression| contains(&expression.phrase))
+        {
+            return Match::Expression;
+        }
+        Match::Text
+    }
+
+    /// Definition with given headword and nothing else, to be completed by user.
+    pub fn new(word: &str) -> Self {
+        Self {
+            word: word.to_string(),
+            key: String::new(),
+            part_of_speech: None,
+            meanings: Vec::new(),
+            expressions: Vec::new(),
+            synonyms: Vec::new(),
+            antonyms: Vec::new(),
+            translations: Vec::new(),
+            forms: Vec::new(),
+        }
+    }
+
+    /// Trim text fields, drop empty optional ones and derive key from headword.
+    pub fn normalize(&mut self) {
+        fn optional(value: &mut Option<String>) {
+            *value = value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string);
+        }
+        self.word = self.word.trim().to_string();
+        self.key = fold(&self.word);
+        optional(&mut self.part_of_speech);
+        for meaning in &mut self.meanings {
+            meaning.definition = meaning.definition.trim().to_string();
+            for example in &mut meaning.examples {
+                example.text = example.text.trim().to_string();
+                optional(&mut example.source);
+            }
+        }
+        for expression in &mut self.expressions {
+            expression.phrase = expression.phrase.trim().to_string();
+            expression.definition = expression.definition.trim().to_string();
+        }
+        for words in [&mut self.synonyms, &mut self.antonyms] {
+            *words = words
+                .iter()
+                .map(|word| word.trim().to_string())
+                .filter(|word| !word.is_empty())
+                .collect();
+        }
+        for translation in &mut self.translations {
+            translation.language = translation.language.trim().to_string();
+            translation.text = translation.text.trim().to_string();
+        }
+        self.translations
+            .retain(|translation| !translation.text.is_empty());
+        for form in &mut self.forms {
+            form.label = form.label.trim().to_string();
+            form.form = form.form.trim().to_string();
+        }
+        self.forms.retain(|form| !form.form.is_empty());
+    }
+
+    /// Check that headword and all texts are present and that there is at least one meaning
+    /// or expression.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| {
+            AppError::Dictionary(format!("invalid definition of {}: {reason}", self.word))
+        };
+        if self.word.trim().is_empty() {
+            return Err(AppError::Dictionary("definition without word".to_string()));
+        }
+        if self.meanings.is_empty() && self.expressions.is_empty() {
+            return Err(invalid("no meanings or expressions".to_string()));
+        }
+        for (numbering, meaning) in (1..).zip(&self.meanings) {
+            if meaning.definition.trim().is_empty() {
+                return Err(invalid(format!("meaning {numbering} has empty definition")));
+            }
+            if meaning
+                .examples
+                .iter()
+                .any(|example| example.text.trim().is_empty())
+            {
+                return Err(invalid(format!("meaning {numbering} has empty example")));
+            }
+        }
+        for (numbering, expression) in (1..).zip(&self.expressions) {
+            if expression.phrase.trim().is_empty() || expression.definition.trim().is_empty() {
+                return Err(invalid(format!(
+                    "expression {numbering} has empty phrase or definition"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Definitions written by hand, YAML or JSON, either a single definition or a list.
+pub fn parse_definitions(text: &str) -> Result<Vec<Definition>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Definitions {
+        One(Box<Definition>),
+        Many(Vec<Definition>),
+    }
+    Ok(match serde_yaml::from_str(text)? {
+        Definitions::One(definition) => vec![*definition],
+        Definitions::Many(definitions) => definitions,
+    })
+}
+
+/// Sort definitions by how they match word, keeping store order for equal matches.
+pub fn rank(word: &str, mut definitions: Vec<Definition>) -> Vec<Definition> {
+    definitions.sort_by_key(|definition| definition.matches(word));
+    definitions
+}
+
+/// Definitions of word as headword, ignoring case and diacritics, exact matches first.
+pub async fn entries(store: &dyn DictionaryStore, word: &str) -> Result<Vec<Definition>> {
+    trace!(
+        "dictionary::entries(store: &dyn DictionaryStore, word: &str) -> Result<Vec<Definition>>"
+    );
+    let mut definitions = rank(word, store.search(word).await?);
+    definitions.retain(|definition| definition.matches(word) <= Match::Folded);
+    Ok(definitions)
+}
+
+/// Definitions of word as headword for commands that can answer without the dictionary; store
+/// errors, e.g. database not running, are logged and taken as no definitions.
+pub async fn available_entries(word: &str) -> Vec<Definition> {
+    trace!("dictionary::available_entries(word: &str) -> Vec<Definition>");
+    let result = async { entries(store()?.as_ref(), word).await }.await;
+    result.unwrap_or_else(|e| {
+        warn!("dictionary not available, answering without it: {e}");
+        Vec::new()
+    })
+}
+
+/// Headwords starting with word, ignoring case and diacritics, shortest first.
+pub fn prefixed(word: &str, headwords: &[String]) -> Vec<String> {
+    let word = fold(word);
+    let mut matches: Vec<String> = headwords
+        .iter()
+        .filter(|headword| fold(headword).starts_with(&word))
+        .cloned()
+        .collect();
+    matches.sort_by(|a, b| a.chars().count().cmp(&b.chars().count()).then(a.cmp(b)));
+    matches.dedup();
+    matches
+}
+
+/// Headwords within edit distance of word, closest first; allowed distance grows with word
+/// length so that short words do not match almost anything.
+pub fn similar(word: &str, headwords: &[String]) -> Vec<String> {
+    let word = fold(word);
+    let max_distance = (word.chars().count() / 3).clamp(1, 3);
+    let mut matches: Vec<(usize, &String)> = headwords
+        .iter()
+        .map(|headword| (distance(&word, &fold(headword)), headword))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    matches.sort();
+    let mut matches: Vec<String> = matches.into_iter().map(|(_, word)| word.clone()).collect();
+    matches.dedup();
+    matches
+}
+
+/// Lower case text with diacritics removed, covering Romanian letters, including the legacy
+/// cedilla forms of ș and ț, and common Latin accents.
+pub fn fold(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ă' | 'â' | 'á' | 'à' | 'ä' => 'a',
+            'î' | 'í' | 'ì' | 'ï' => 'i',
+            'ș' | 'ş' => 's',
+            'ț' | 'ţ' => 't',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'ó' | 'ò' | 'ô' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            c => c,
+        })
+        .collect()
+}
+
+/// Levenshtein distance, counted in characters.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dictionary::{
+        Definition, Expression, Match, distance, fold, prefixed, rank, similar,
+    };
+
+    #[test]
+    fn matching() {
+        let definition = Definition {
+            word: "țară".to_string(),
+            key: "tara".to_string(),
+            part_of_speech: None,
+            meanings: Vec::new(),
+            expressions: vec![Expression {
+                phrase: "A se duce în țara cea fără dor".to_string(),
+                definition: "a muri".to_string(),
+            }],
+            ..Definition::new("țară")
+        };
+        assert_eq!(definition.matches("Țară"), Match::Exact);
+        assert_eq!(definition.matches("tară"), Match::Folded);
+        assert_eq!(definition.matches("tara"), Match::Folded);
+        assert_eq!(definition.matches("fara dor"), Match::Expression);
+        assert_eq!(definition.matches("dorul"), Match::Text);
+
+        assert_eq!(fold("Știință ŞI ţară"), "stiinta si tara");
+        assert_eq!(distance("casa", "masa"), 1);
+        assert_eq!(distance("", "abc"), 3);
+        let headwords = ["casă", "casetă", "masă", "cal", "căsuță"].map(String::from);
+        assert_eq!(similar("casa", &headwords), ["casă", "masă"]);
+        assert_eq!(prefixed("cas", &headwords), ["casă", "casetă", "căsuță"]);
+    }
+
+    #[test]
+    fn ranking() {
+        let definitions = vec![
+            Definition {
+                key: "tara".to_string(),
+                ..Definition::new("țară")
+            },
+            Definition {
+                key: "tara".to_string(),
+                ..Definition::new("tara")
+            },
+        ];
+        let words = |word: &str| -> Vec<String> {
+            rank(word, definitions.clone())
+                .into_iter()
+                .map(|definition| definition.word)
+                .collect()
+        };
+        assert_eq!(words("tara"), ["tara", "țară"]);
+        assert_eq!(words("țară"), ["țară", "tara"]);
+    }
+}
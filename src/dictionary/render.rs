@@ -0,0 +1,360 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use log::trace;
+
+use crate::{
+    config::DictionaryConfig,
+    dictionary::{Definition, Lookup},
+    error::Result,
+};
+
+/// Output format of dictionary lookups.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// ANSI styled text: bold headwords, dimmed examples.
+    Terminal,
+    Markdown,
+    Json,
+    /// Standalone HTML document.
+    Html,
+}
+
+impl Format {
+    /// Format by name as accepted by `--format`, none if not known.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "terminal" | "text" => Some(Format::Terminal),
+            "markdown" | "md" => Some(Format::Markdown),
+            "json" => Some(Format::Json),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+
+    /// Styled output on an interactive terminal, unless disabled by `NO_COLOR`, and Markdown
+    /// otherwise, e.g. when piped.
+    pub fn detect() -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        match io::stdout().is_terminal() && !no_color {
+            true => Format::Terminal,
+            false => Format::Markdown,
+        }
+    }
+}
+
+/// Section labels and message templates, in the language of the dictionary.
+pub struct Labels {
+    pub expressions: String,
+    pub generated: String,
+    /// HTML document language.
+    pub language: String,
+    pub more: String,
+    pub suggestions: String,
+    pub synonyms: String,
+    pub antonyms: String,
+    pub none: String,
+}
+
+impl Labels {
+    const EXPRESSIONS: &'static str = "În expresie";
+    const GENERATED: &'static str = "Generat automat, nu provine din dicționar";
+    const LANGUAGE: &'static str = "ro";
+    const MORE: &'static str = "Încă {count}, folosiți --limit pentru a le afișa";
+    const SUGGESTIONS: &'static str = "Nicio definiție pentru {word}. Ați vrut să spuneți {words}?";
+    const SYNONYMS: &'static str = "Sinonime pentru {word}";
+    const ANTONYMS: &'static str = "Antonime pentru {word}";
+    const NONE: &'static str = "Niciun rezultat";
+
+    pub fn new(config: &DictionaryConfig) -> Self {
+        let labels = config.labels.clone().unwrap_or_default();
+        let label = |label: Option<String>, default: &str| label.unwrap_or(default.to_string());
+        Self {
+            expressions: label(labels.expressions, Self::EXPRESSIONS),
+            generated: label(labels.generated, Self::GENERATED),
+            language: label(labels.language, Self::LANGUAGE),
+            more: label(labels.more, Self::MORE),
+            suggestions: label(labels.suggestions, Self::SUGGESTIONS),
+            synonyms: label(labels.synonyms, Self::SYNONYMS),
+            antonyms: label(labels.antonyms, Self::ANTONYMS),
+            none: label(labels.none, Self::NONE),
+        }
+    }
+}
+
+pub fn render(lookup: &Lookup, format: Format, labels: &Labels) -> Result<String> {
+    trace!("render::render(lookup: &Lookup, format: Format, labels: &Labels) -> Result<String>");
+    Ok(match format {
+        Format::Terminal => terminal(lookup, labels),
+        Format::Markdown => markdown(lookup, labels),
+        Format::Json => serde_json::to_string_pretty(lookup)?,
+        Format::Html => html(lookup, labels),
+    })
+}
+
+/// Markdown answer written by SLM, preceded by the generated notice.
+pub fn generated(answer: &str, labels: &Labels) -> String {
+    format!("_{}_\n\n{}", labels.generated, answer.trim())
+}
+
+fn markdown(lookup: &Lookup, labels: &Labels) -> String {
+    let mut lines = Vec::<String>::new();
+    if lookup.generated {
+        lines.push(format!("_{}_", labels.generated));
+    }
+    for definition in &lookup.definitions {
+        if !definition.meanings.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("# {}", headword(definition)));
+            lines.push(String::new());
+
+            for (numbering, meaning) in (1..).zip(&definition.meanings) {
+                lines.push(format!("{}. {}", numbering, meaning.definition));
+                lines.push(String::new());
+
+                for example in &meaning.examples {
+                    lines.push(format!("> {}  ", example.text));
+                    if let Some(source) = &example.source {
+                        lines.push(format!("\u{2014} _{}_", source));
+                    }
+                    lines.push(String::new());
+                }
+            }
+
+            if !definition.expressions.is_empty() {
+                lines.push(format!("## {}", labels.expressions));
+            }
+        }
+
+        for expression in &definition.expressions {
+            lines.push(format!(
+                "- __{}__: {}",
+                expression.phrase, expression.definition
+            ));
+        }
+        if !definition.expressions.is_empty() {
+            lines.push(String::new());
+        }
+    }
+    if lookup.more > 0 {
+        lines.push(format!("_{}_", more(lookup, labels)));
+    }
+    if let Some(suggestions) = suggestions(lookup, labels, |word| format!("__{word}__")) {
+        lines.push(suggestions);
+    }
+    lines.join("\n")
+}
+
+fn terminal(lookup: &Lookup, labels: &Labels) -> String {
+    const BOLD: &str = "\x1b[1m";
+    const DIM: &str = "\x1b[2m";
+    const ITALIC: &str = "\x1b[3m";
+    const RESET: &str = "\x1b[0m";
+
+    let mut lines = Vec::<String>::new();
+    if lookup.generated {
+        lines.push(format!("{DIM}{ITALIC}{}{RESET}", labels.generated));
+    }
+    for definition in &lookup.definitions {
+        lines.push(String::new());
+        lines.push(match &definition.part_of_speech {
+            Some(part_of_speech) => format!(
+                "{BOLD}{}{RESET} {ITALIC}{}{RESET}",
+                definition.word,
+                part_of_speech.to_lowercase()
+            ),
+            None => format!("{BOLD}{}{RESET}", definition.word),
+        });
+        for (numbering, meaning) in (1..).zip(&definition.meanings) {
+            lines.push(format!("  {numbering}. {}", meaning.definition));
+            for example in &meaning.examples {
+                let source = match &example.source {
+                    Some(source) => format!(" \u{2014} {source}"),
+                    None => String::new(),
+                };
+                lines.push(format!("     {DIM}{}{source}{RESET}", example.text));
+            }
+        }
+        if !definition.expressions.is_empty() {
+            if !definition.meanings.is_empty() {
+                lines.push(format!("  {BOLD}{}{RESET}", labels.expressions));
+            }
+            for expression in &definition.expressions {
+                lines.push(format!(
+                    "  \u{2022} {BOLD}{}{RESET}: {}",
+                    expression.phrase, expression.definition
+                ));
+            }
+        }
+    }
+    if lookup.more > 0 {
+        lines.push(String::new());
+        lines.push(format!("{DIM}{}{RESET}", more(lookup, labels)));
+    }
+    if let Some(suggestions) = suggestions(lookup, labels, |word| format!("{BOLD}{word}{RESET}")) {
+        lines.push(suggestions);
+    }
+    lines.join("\n")
+}
+
+fn html(lookup: &Lookup, labels: &Labels) -> String {
+    let mut body = Vec::<String>::new();
+    if lookup.generated {
+        body.push(format!("<p><em>{}</em></p>", escape(&labels.generated)));
+    }
+    for definition in &lookup.definitions {
+        body.push("<article>".to_string());
+        body.push(format!("<h1>{}</h1>", escape(&headword(definition))));
+        if !definition.meanings.is_empty() {
+            body.push("<ol>".to_string());
+            for meaning in &definition.meanings {
+                body.push(format!("<li><p>{}</p>", escape(&meaning.definition)));
+                for example in &meaning.examples {
+                    let source = match &example.source {
+                        Some(source) => format!(" <cite>{}</cite>", escape(source)),
+                        None => String::new(),
+                    };
+                    body.push(format!(
+                        "<blockquote>{}{source}</blockquote>",
+                        escape(&example.text)
+                    ));
+                }
+                body.push("</li>".to_string());
+            }
+            body.push("</ol>".to_string());
+        }
+        if !definition.expressions.is_empty() {
+            body.push(format!("<h2>{}</h2>", escape(&labels.expressions)));
+            body.push("<ul>".to_string());
+            for expression in &definition.expressions {
+                body.push(format!(
+                    "<li><strong>{}</strong>: {}</li>",
+                    escape(&expression.phrase),
+                    escape(&expression.definition)
+                ));
+            }
+            body.push("</ul>".to_string());
+        }
+        body.push("</article>".to_string());
+    }
+    if lookup.more > 0 {
+        body.push(format!("<p><em>{}</em></p>", escape(&more(lookup, labels))));
+    }
+    if let Some(suggestions) = suggestions(lookup, labels, |word| {
+        format!("<strong>{}</strong>", escape(word))
+    }) {
+        body.push(format!("<p>{suggestions}</p>"));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}\n</body>\n</html>",
+        escape(&labels.language),
+        escape(&lookup.word),
+        body.join("\n")
+    )
+}
+
+fn headword(definition: &Definition) -> String {
+    match &definition.part_of_speech {
+        Some(part_of_speech) => format!("{}, {}", definition.word, part_of_speech.to_lowercase()),
+        None => definition.word.clone(),
+    }
+}
+
+fn more(lookup: &Lookup, labels: &Labels) -> String {
+    labels.more.replace("{count}", &lookup.more.to_string())
+}
+
+/// Did you mean line with suggested words styled by given function, none without suggestions.
+fn suggestions(lookup: &Lookup, labels: &Labels, style: impl Fn(&str) -> String) -> Option<String> {
+    if lookup.suggestions.is_empty() {
+        return None;
+    }
+    let words: Vec<String> = lookup.suggestions.iter().map(|word| style(word)).collect();
+    Some(
+        labels
+            .suggestions
+            .replace("{words}", &words.join(", "))
+            .replace("{word}", &lookup.word),
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::DictionaryConfig;
+    use crate::dictionary::{
+        Definition, Example, Expression, Lookup, Meaning,
+        render::{Format, Labels, render},
+    };
+
+    #[test]
+    fn formats() {
+        let lookup = Lookup {
+            word: "casă".to_string(),
+            definitions: vec![Definition {
+                word: "casă".to_string(),
+                key: "casa".to_string(),
+                part_of_speech: Some("Substantiv".to_string()),
+                meanings: vec![Meaning {
+                    definition: "Clădire de locuit.".to_string(),
+                    examples: vec![Example {
+                        text: "O casă <mare>.".to_string(),
+                        source: Some("DEX".to_string()),
+                    }],
+                }],
+                expressions: vec![Expression {
+                    phrase: "casă de bani".to_string(),
+                    definition: "seif".to_string(),
+                }],
+                ..Definition::new("casă")
+            }],
+            more: 0,
+            suggestions: Vec::new(),
+            generated: true,
+        };
+        let labels = Labels {
+            expressions: "Expressions".to_string(),
+            generated: "Generated".to_string(),
+            language: "en".to_string(),
+            ..Labels::new(&DictionaryConfig::default())
+        };
+
+        let markdown = render(&lookup, Format::Markdown, &labels).unwrap();
+        assert!(markdown.contains("# casă, substantiv\n\n1. Clădire de locuit."));
+        assert!(markdown.contains("## Expressions\n- __casă de bani__: seif"));
+        assert!(markdown.starts_with("_Generated_\n"));
+        let terminal = render(&lookup, Format::Terminal, &labels).unwrap();
+        assert!(terminal.contains("\x1b[1mcasă\x1b[0m \x1b[3msubstantiv\x1b[0m"));
+        assert!(terminal.contains("\x1b[2mO casă <mare>. \u{2014} DEX\x1b[0m"));
+        let html = render(&lookup, Format::Html, &labels).unwrap();
+        assert!(html.contains("<html lang=\"en\">"));
+        assert!(html.contains("<blockquote>O casă &lt;mare&gt;. <cite>DEX</cite></blockquote>"));
+        let json = render(&lookup, Format::Json, &labels).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()["definitions"][0]["key"],
+            "casa"
+        );
+
+        let lookup = Lookup {
+            definitions: Vec::new(),
+            more: 2,
+            suggestions: vec!["casa".to_string(), "cască".to_string()],
+            generated: false,
+            ..lookup
+        };
+        let markdown = render(&lookup, Format::Markdown, &labels).unwrap();
+        assert!(markdown.contains("_Încă 2, folosiți --limit pentru a le afișa_"));
+        assert!(
+            markdown
+                .contains("Nicio definiție pentru casă. Ați vrut să spuneți __casa__, __cască__?")
+        );
+    }
+}
@@ -10,9 +10,18 @@ pub enum AppError {
     #[error("Config error")]
     ConfigError,
 
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    #[error("Unknown SLM backend: {0}")]
+    UnknownBackend(String),
+
     #[error("Serde YAML: {0}")]
     Yaml(#[from] serde_yaml::Error),
 
+    #[error("Serde JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
     #[error("Parse command error")]
     ParseCommandError,
 
@@ -25,9 +34,36 @@ pub enum AppError {
     #[error("Regex error: {0}")]
     Regex(#[from] regex::Error),
 
+    #[error("Readline error: {0}")]
+    Readline(#[from] rustyline::error::ReadlineError),
+
     #[error("MongoDB error: {0}")]
     Mongo(#[from] mongodb::error::Error),
 
+    #[error("Not supported by SLM backend: {0}")]
+    Unsupported(String),
+
+    #[error("Attachment error: {0}")]
+    Attachment(String),
+
+    #[error("Session error: {0}")]
+    Session(String),
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Server error: {0}")]
+    Server(String),
+
+    #[error("Tool error: {0}")]
+    Tool(String),
+
+    #[error("Dictionary error: {0}")]
+    Dictionary(String),
+
+    #[error("User command error: {0}")]
+    UserCommand(String),
+
     #[error("Unrecoverable error on {0}")]
     Fatal(String),
 }
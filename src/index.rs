@@ -0,0 +1,266 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{AppError, Result},
+    slm::SlmBackend,
+    util::dirs,
+};
+
+/// Local retrieval index: documents split into chunks with embedding vectors computed by the
+/// SLM backend, stored as JSON under the data directory.
+#[derive(Serialize, Deserialize)]
+pub struct Index {
+    name: String,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Chunk {
+    pub source: String,
+    pub line: usize,
+    pub text: String,
+    vector: Vec<f32>,
+}
+
+impl Index {
+    const INDEXES_DIR: &'static str = "indexes";
+    const EXTENSION: &'static str = "json";
+    /// Soft chunk size in characters; chunks are cut at paragraph boundaries when possible.
+    const CHUNK_SIZE: usize = 1200;
+    const BATCH_SIZE: usize = 32;
+    const EXTENSIONS: &'static [&'static str] = &[
+        "md", "markdown", "txt", "rst", "adoc", "rs", "py", "js", "ts", "java", "go", "c", "h",
+        "cpp", "hpp", "cs", "kt", "rb", "php", "sh", "sql", "toml", "yml", "yaml", "json", "xml",
+        "html", "css",
+    ];
+    const SKIP_DIRS: &'static [&'static str] = &["target", "node_modules"];
+
+    pub fn exists(name: &str) -> bool {
+        Self::path(name).is_ok_and(|path| path.exists())
+    }
+
+    /// Names of indexes stored in the data directory.
+    pub fn names() -> Result<Vec<String>> {
+        trace!("Index::names() -> Result<Vec<String>>");
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dirs::data_dir(Self::INDEXES_DIR)?)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == Self::EXTENSION)
+                && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn load(name: &str) -> Result<Self> {
+        trace!("Index::load(name: &str) -> Result<Self>");
+        let json = fs::read_to_string(Self::path(name)?)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Ingest text files found under given paths, recursively, and embed their chunks.
+    pub async fn build(name: &str, paths: &[String], backend: &dyn SlmBackend) -> Result<Self> {
+        trace!(
+            "Index::build(name: &str, paths: &[String], backend: &dyn SlmBackend) -> Result<Self>"
+        );
+        let mut files = Vec::new();
+        for path in paths {
+            collect_files(Path::new(path), &mut files)?;
+        }
+        debug!("index {name}: {} files", files.len());
+
+        let mut chunks = Vec::new();
+        for file in &files {
+            let Ok(text) = fs::read_to_string(file) else {
+                warn!("skip not UTF-8 file {}", file.display());
+                continue;
+            };
+            for (line, text) in split(&text, Self::CHUNK_SIZE) {
+                chunks.push(Chunk {
+                    source: file.display().to_string(),
+                    line,
+                    text,
+                    vector: Vec::new(),
+                });
+            }
+        }
+
+        for batch in chunks.chunks_mut(Self::BATCH_SIZE) {
+            let inputs: Vec<String> = batch.iter().map(|chunk| chunk.text.clone()).collect();
+            let vectors = backend.embed(&inputs).await?;
+            if vectors.len() != batch.len() {
+                return Err(AppError::Fatal(format!(
+                    "expected {} embeddings but got {}",
+                    batch.len(),
+                    vectors.len()
+                )));
+            }
+            for (chunk, vector) in batch.iter_mut().zip(vectors) {
+                chunk.vector = vector;
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            chunks,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        trace!("Index::save(&self) -> Result<()>");
+        fs::write(Self::path(&self.name)?, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn chunks_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn sources_count(&self) -> usize {
+        let sources: HashSet<&str> = self
+            .chunks
+            .iter()
+            .map(|chunk| chunk.source.as_str())
+            .collect();
+        sources.len()
+    }
+
+    /// Chunks most similar to the query vector, best first.
+    pub fn search(&self, vector: &[f32], top_k: usize) -> Result<Vec<&Chunk>> {
+        trace!("Index::search(&self, vector: &[f32], top_k: usize) -> Result<Vec<&Chunk>>");
+        if let Some(chunk) = self.chunks.first()
+            && chunk.vector.len() != vector.len()
+        {
+            return Err(AppError::Fatal(format!(
+                "index {} was built with a different embedding model",
+                self.name
+            )));
+        }
+        let mut scored: Vec<(f32, &Chunk)> = self
+            .chunks
+            .iter()
+            .map(|chunk| (cosine(&chunk.vector, vector), chunk))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored
+            .into_iter()
+            .take(top_k)
+            .map(|(_, chunk)| chunk)
+            .collect())
+    }
+
+    fn path(name: &str) -> Result<PathBuf> {
+        dirs::data_file(Self::INDEXES_DIR, name, Self::EXTENSION)
+    }
+}
+
+/// Collect text files from given path, recursively if a directory, skipping hidden entries,
+/// build directories and files with not known text extensions.
+pub fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    if !path.is_dir() {
+        return Err(AppError::Fatal(format!("{} not found", path.display())));
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        let name = entry.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        if entry.is_dir() {
+            if !Index::SKIP_DIRS.contains(&name.as_ref()) {
+                collect_files(&entry, files)?;
+            }
+        } else if entry
+            .extension()
+            .is_some_and(|ext| Index::EXTENSIONS.contains(&ext.to_string_lossy().as_ref()))
+        {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// Split text into chunks of about given size, returning each chunk with its 1-based start
+/// line. Chunks end on a blank line once half full, or before a line that would overflow.
+fn split(text: &str, size: usize) -> Vec<(usize, String)> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut start = 0;
+    for (number, line) in text.lines().enumerate() {
+        let blank = line.trim().is_empty();
+        if !chunk.is_empty()
+            && ((blank && chunk.len() >= size / 2) || chunk.len() + line.len() > size)
+        {
+            chunks.push((start, chunk.trim_end().to_string()));
+            chunk.clear();
+        }
+        if chunk.is_empty() {
+            if blank {
+                continue;
+            }
+            start = number + 1;
+        }
+        chunk.push_str(line);
+        chunk.push('\n');
+    }
+    if !chunk.trim().is_empty() {
+        chunks.push((start, chunk.trim_end().to_string()));
+    }
+    chunks
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm = norm_a * norm_b;
+    if norm == 0.0 { 0.0 } else { dot / norm }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::index::{cosine, split};
+
+    #[test]
+    fn split_paragraphs() {
+        let text = "# Title\n\nfirst paragraph\nstill first\n\n\nsecond paragraph\n";
+        let chunks = split(text, 20);
+        assert_eq!(
+            chunks,
+            vec![
+                (1, "# Title".to_string()),
+                (3, "first paragraph".to_string()),
+                (4, "still first".to_string()),
+                (7, "second paragraph".to_string()),
+            ]
+        );
+        let chunks = split(text, 1000);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].0, 1);
+    }
+
+    #[test]
+    fn cosine_similarity() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-6);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+}
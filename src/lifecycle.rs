@@ -0,0 +1,363 @@
+use std::{
+    fmt,
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info, trace, warn};
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use tokio::process::Command;
+
+use crate::{
+    config::{Server, Shutdown},
+    error::{AppError, Result},
+    slm::ServiceStatus,
+    util::net,
+};
+
+/// Power and readiness state of a server, in the order they are reached while starting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum State {
+    Sleeping,
+    Waking,
+    HostUp,
+    ServiceUp,
+    ModelLoaded,
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            State::Sleeping => "sleeping",
+            State::Waking => "waking",
+            State::HostUp => "host up",
+            State::ServiceUp => "service up, loading model",
+            State::ModelLoaded => "model loaded",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Start and stop sequences of a configured server. On start, Wake-on-LAN is sent, and re-sent
+/// periodically while the host is not up, then host port and HTTP health endpoint are probed
+/// until the model is loaded or the overall deadline expires. On stop, an authenticated shutdown
+/// is requested and the host port probed until it is closed.
+pub struct Lifecycle<'a> {
+    server: &'a Server,
+    http_client: Client,
+}
+
+impl<'a> Lifecycle<'a> {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+    const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+    const SSH_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new(server: &'a Server) -> Result<Self> {
+        let http_client = Client::builder().timeout(Self::PROBE_TIMEOUT).build()?;
+        Ok(Self {
+            server,
+            http_client,
+        })
+    }
+
+    /// Current state, from the most advanced probe that succeeds. A sleeping host cannot be
+    /// told apart from a waking one, so this never returns [State::Waking].
+    pub async fn probe(&self) -> State {
+        trace!("Lifecycle::probe(&self) -> State");
+        match self.health().await {
+            Some(status) if status.is_success() => return State::ModelLoaded,
+            Some(_) => return State::ServiceUp,
+            None => {}
+        }
+        if self.host_up().await {
+            return State::HostUp;
+        }
+        State::Sleeping
+    }
+
+    /// Whether host port accepts connections.
+    async fn host_up(&self) -> bool {
+        trace!("Lifecycle::host_up(&self) -> bool");
+        let server = self.server;
+        net::probe(&server.host, server.host_port, Self::CONNECT_TIMEOUT)
+            .await
+            .is_open()
+    }
+
+    /// HTTP status of the health endpoint, none if service is not reachable.
+    pub async fn health(&self) -> Option<StatusCode> {
+        trace!("Lifecycle::health(&self) -> Option<StatusCode>");
+        match self.http_client.get(&self.server.health_url).send().await {
+            Ok(response) => {
+                debug!("{} health status {}", self.server.name, response.status());
+                Some(response.status())
+            }
+            Err(e) => {
+                debug!("{} health probe: {e}", self.server.name);
+                None
+            }
+        }
+    }
+
+    /// Service status from the configured status endpoint, none if not configured.
+    pub async fn service_status(&self) -> Option<Result<ServiceStatus>> {
+        trace!("Lifecycle::service_status(&self) -> Option<Result<ServiceStatus>>");
+        let url = self.server.status_url.as_ref()?;
+        let mut builder = self.http_client.get(url);
+        if let Some(token) = &self.server.api_token {
+            builder = builder.bearer_auth(token);
+        }
+        let status = async {
+            let response = builder.send().await?.error_for_status()?;
+            Ok(response.json().await?)
+        };
+        Some(status.await)
+    }
+
+    /// Drive the server up to [State::ModelLoaded], reporting every state change with time
+    /// elapsed since start. Fails if the model is not loaded before the configured deadline.
+    pub async fn start(&self, progress: impl Fn(State, Duration)) -> Result<()> {
+        trace!("Lifecycle::start(&self, progress: impl Fn(State, Duration)) -> Result<()>");
+        let server = self.server;
+        let started = Instant::now();
+        let mut state = self.probe().await;
+        progress(state, started.elapsed());
+        let mut last_wol: Option<Instant> = None;
+
+        while state != State::ModelLoaded {
+            if started.elapsed() >= server.wake_timeout {
+                return Err(AppError::Server(format!(
+                    "{} not ready after {}s, last state: {state}",
+                    server.name,
+                    server.wake_timeout.as_secs()
+                )));
+            }
+            if state < State::HostUp
+                && last_wol.is_none_or(|sent| sent.elapsed() >= server.wol_interval)
+            {
+                info!("send Wake-on-LAN to {}", server.name);
+                server.wol.send().await?;
+                last_wol = Some(Instant::now());
+            }
+
+            tokio::time::sleep(Self::POLL_INTERVAL).await;
+            let probed = match self.probe().await {
+                State::Sleeping if last_wol.is_some() => State::Waking,
+                probed => probed,
+            };
+            if probed != state {
+                state = probed;
+                progress(state, started.elapsed());
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of requests being processed, from llama.cpp slots; none if not configured or
+    /// not available.
+    pub async fn in_flight(&self) -> Option<usize> {
+        trace!("Lifecycle::in_flight(&self) -> Option<usize>");
+        let url = self.server.slots_url.as_ref()?;
+        let mut builder = self.http_client.get(url);
+        if let Some(token) = &self.server.api_token {
+            builder = builder.bearer_auth(token);
+        }
+        let slots: Vec<Value> = match builder.send().await {
+            Ok(response) => response.error_for_status().ok()?.json().await.ok()?,
+            Err(e) => {
+                debug!("{} slots probe: {e}", self.server.name);
+                return None;
+            }
+        };
+        // recent llama.cpp reports is_processing, older versions a non zero state
+        let busy = slots
+            .iter()
+            .filter(|slot| {
+                slot["is_processing"].as_bool().unwrap_or(false)
+                    || slot["state"].as_u64().is_some_and(|state| state != 0)
+            })
+            .count();
+        Some(busy)
+    }
+
+    /// Request authenticated shutdown and wait for the host port to close. Errors while
+    /// requesting are reported only if the host is still up at deadline, since the connection
+    /// may be dropped by the host going down.
+    pub async fn stop(&self, progress: impl Fn(State, Duration)) -> Result<()> {
+        trace!("Lifecycle::stop(&self, progress: impl Fn(State, Duration)) -> Result<()>");
+        let server = self.server;
+        let shutdown = server.shutdown()?;
+        let started = Instant::now();
+        let requested = self.request_shutdown(shutdown).await;
+        if let Err(e) = &requested {
+            warn!("{} shutdown request: {e}", server.name);
+        }
+
+        while self.host_up().await {
+            if started.elapsed() >= server.stop_timeout {
+                let reason = match requested {
+                    Ok(()) => String::new(),
+                    Err(e) => format!(", shutdown request failed: {e}"),
+                };
+                return Err(AppError::Server(format!(
+                    "{} still up after {}s{reason}",
+                    server.name,
+                    server.stop_timeout.as_secs()
+                )));
+            }
+            tokio::time::sleep(Self::POLL_INTERVAL).await;
+        }
+        progress(State::Sleeping, started.elapsed());
+        Ok(())
+    }
+
+    async fn request_shutdown(&self, shutdown: &Shutdown) -> Result<()> {
+        trace!("Lifecycle::request_shutdown(&self, shutdown: &Shutdown) -> Result<()>");
+        match shutdown {
+            Shutdown::Http { url, token } => {
+                let response = self.http_client.post(url).bearer_auth(token).send().await?;
+                response.error_for_status()?;
+            }
+            Shutdown::Ssh {
+                destination,
+                key,
+                port,
+                command,
+            } => {
+                // a hung ssh, e.g. to a host going half down, must not outlast the stop timeout
+                let timeout = self.server.stop_timeout;
+                let connect_timeout = timeout.min(Self::SSH_CONNECT_TIMEOUT).as_secs().max(1);
+                let mut ssh = Command::new("ssh");
+                ssh.args(["-o", "BatchMode=yes", "-o"])
+                    .arg(format!("ConnectTimeout={connect_timeout}"))
+                    .arg("-p")
+                    .arg(port.to_string())
+                    .stdin(Stdio::null())
+                    .kill_on_drop(true);
+                if let Some(key) = key {
+                    ssh.arg("-i").arg(key);
+                }
+                let output =
+                    tokio::time::timeout(timeout, ssh.arg(destination).arg(command).output())
+                        .await
+                        .map_err(|_| {
+                            AppError::Server(format!(
+                                "ssh {destination} not done after {}s",
+                                timeout.as_secs()
+                            ))
+                        })??;
+                if !output.status.success() {
+                    return Err(AppError::Server(format!(
+                        "ssh {destination} exited with {}: {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use crate::config::{AppConfig, Server};
+    use crate::lifecycle::{Lifecycle, State};
+
+    fn server(host_port: u16, health_port: u16, wake_timeout: u64) -> Server {
+        let yaml = format!(
+            "
+servers:
+  box:
+    host: 127.0.0.1
+    mac: 10-7c-61-5f-10-be
+    host_port: {host_port}
+    health_url: http://127.0.0.1:{health_port}/health
+    wake_timeout: {wake_timeout}
+"
+        );
+        let config: AppConfig = serde_yaml::from_str(&yaml).unwrap();
+        config.server(None).unwrap()
+    }
+
+    /// Port nothing listens on.
+    fn closed_port() -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    /// Fake health endpoint answering with given statuses in order, the last one repeated.
+    async fn health(statuses: Vec<u16>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for index in 0.. {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                let status = statuses[index.min(statuses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn probe() {
+        let server = server(closed_port(), closed_port(), 0);
+        assert_eq!(
+            Lifecycle::new(&server).unwrap().probe().await,
+            State::Sleeping
+        );
+
+        let host = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host_port = host.local_addr().unwrap().port();
+        let server = self::server(host_port, closed_port(), 0);
+        assert_eq!(
+            Lifecycle::new(&server).unwrap().probe().await,
+            State::HostUp
+        );
+
+        let server = self::server(host_port, health(vec![503, 200]).await, 0);
+        let lifecycle = Lifecycle::new(&server).unwrap();
+        assert_eq!(lifecycle.probe().await, State::ServiceUp);
+        assert_eq!(lifecycle.probe().await, State::ModelLoaded);
+    }
+
+    #[tokio::test]
+    async fn start() {
+        let host = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host_port = host.local_addr().unwrap().port();
+        let server = server(host_port, health(vec![503, 503, 200]).await, 10);
+        let states = RefCell::new(Vec::new());
+        Lifecycle::new(&server)
+            .unwrap()
+            .start(|state, _| states.borrow_mut().push(state))
+            .await
+            .unwrap();
+        assert_eq!(states.into_inner(), [State::ServiceUp, State::ModelLoaded]);
+
+        let server = self::server(host_port, health(vec![503]).await, 0);
+        let error = Lifecycle::new(&server)
+            .unwrap()
+            .start(|_, _| {})
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Server error: box not ready after 0s, last state: service up, loading model"
+        );
+    }
+}
@@ -1,21 +1,31 @@
 mod agent;
+mod attachment;
+mod chat;
 mod command;
 mod config;
+mod dictionary;
 mod error;
+mod index;
+mod lifecycle;
 mod logger;
+mod router;
+mod session;
 mod slm;
+mod standby;
+mod tool;
 mod util;
 
 use crate::{
-    agent::{prompt::PromptAgent, rag::RagAgent},
-    command::Command,
-    error::Result,
-    slm::SlmRequest,
+    agent::cag::CagAgent,
+    attachment::Attachment,
+    chat::Chat,
+    error::{AppError, Result},
+    router::Router,
+    session::Session,
+    standby::{Standby, StandbyBackend},
 };
 use clap::Parser;
-use futures_util::StreamExt;
 use log::{debug, trace};
-use tokio::io::{self, AsyncWriteExt};
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -46,6 +56,43 @@ struct Args {
     #[arg(short, long, help = "RAG context name")]
     context: Option<String>,
 
+    #[arg(long, value_name = "CORPUS", help = "CAG corpus name from config")]
+    cag: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "SLM backend name from config -- if not specified use default one"
+    )]
+    backend: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "false",
+        help = "interactive chat, default if no prompt nor attachments"
+    )]
+    chat: bool,
+
+    #[arg(long, help = "named conversation session persisted on local disk")]
+    session: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "false",
+        help = "print routing decision and its reason"
+    )]
+    explain_route: bool,
+
+    #[arg(
+        long = "file",
+        value_name = "PATH",
+        help = "attach text file to prompt, repeatable"
+    )]
+    files: Vec<String>,
+
+    #[arg(long = "idle-watch", value_name = "SERVER", hide = true)]
+    idle_watch: Option<String>,
+
     // all remaining arguments as prompt
     #[arg(trailing_var_arg = true)]
     prompt: Vec<String>,
@@ -56,10 +103,10 @@ impl Args {
         let mut prompt = String::new();
         let mut iter = self.prompt.iter();
 
-        if let Some(first) = iter.next() {
-            if first.trim() != "," {
-                prompt.push_str(first);
-            }
+        if let Some(first) = iter.next()
+            && first.trim() != ","
+        {
+            prompt.push_str(first);
         }
         for part in iter {
             if !prompt.is_empty() {
@@ -73,47 +120,60 @@ impl Args {
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let args = Args::parse();
     logger::init(&args.log_level, &args.log_file);
-    trace!("main() -> Result<()>");
+    trace!("main()");
+    if let Err(e) = run(args).await {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
 
+async fn run(args: Args) -> Result<()> {
+    trace!("run(args: Args) -> Result<()>");
     config::init_config(&args.config_file)?;
     let config = config::get_config();
     debug!("config: {config:?}");
-
-    let prompt = args.prompt();
-    if let Some(command) = prompt.parse::<Command>().ok() {
-        if let Some(response) = command.exec().await? {
-            println!("{response}");
-            return Ok(());
-        };
+    if let Some(server) = &args.idle_watch {
+        return Standby::watch(server).await;
     }
 
-    let context = args.context.clone();
-    let mut request = SlmRequest::new(&prompt);
-    if let Some(system) = args.system {
-        request.set_system(&system);
-    }
-    if let Some(profile) = &config.user_profile {
-        request.set_profile(&profile);
-    }
-    if let Some(settings) = &config.system_settings {
-        request.set_settings(&settings);
-    }
-    if let Some(context) = args.context {
-        request.set_context(&context);
-    }
+    let session = match &args.session {
+        Some(name) => Some(Session::open(name)?),
+        None => None,
+    };
 
-    let mut stream = match context {
-        Some(_) => RagAgent::new().exec(request).await,
-        None => PromptAgent::new().exec(request).await,
+    let backend = slm::backend(args.backend.as_deref())?;
+    let backend = StandbyBackend::wrap(backend, args.backend.as_deref());
+    let router = Router::new(backend.clone(), args.explain_route)?;
+    let cag = match &args.cag {
+        Some(corpus) => Some(CagAgent::new(backend.clone(), corpus)?),
+        None => None,
     };
+    let prompt = args.prompt();
+    let mut attachments = Vec::new();
+    if args.chat && !args.files.is_empty() {
+        return Err(AppError::Attachment(
+            "files cannot be attached to interactive chat".to_string(),
+        ));
+    }
+    // in interactive chat standard input holds the user prompts
+    if !args.chat {
+        let limit = config.attachment_limit.unwrap_or(Attachment::LIMIT);
+        for file in &args.files {
+            attachments.push(Attachment::from_file(file, limit)?);
+        }
+        if let Some(stdin) = Attachment::from_stdin(limit)? {
+            attachments.insert(0, stdin);
+        }
+    }
 
-    while let Some(chunk) = stream.next().await {
-        print!("{}", chunk?);
-        let _ = io::stdout().flush().await;
+    let mut chat = Chat::new(backend, router, args.system, args.context, cag, session);
+    if args.chat || (prompt.is_empty() && attachments.is_empty()) {
+        return chat.run().await;
     }
+    chat.exchange(&prompt, &attachments).await?;
 
     Ok(())
 }
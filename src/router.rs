@@ -0,0 +1,285 @@
+use std::{fmt, rc::Rc};
+
+use log::{debug, info, trace, warn};
+use regex::Regex;
+
+use crate::{
+    command::{Command, user::UserCommand},
+    config,
+    error::Result,
+    index::Index,
+    slm::{self, SlmBackend, SlmRequest},
+};
+
+/// Where a prompt is sent: a command, plain question answering, or a question over a known
+/// corpus, answered either by retrieval or by cache-augmented generation. A user command prompt
+/// template replaces prompt and system role before the request takes its inner route.
+pub enum Route {
+    Command(Command),
+    Prompt,
+    Rag(String),
+    Cag(String),
+    Template {
+        prompt: String,
+        system: Option<String>,
+        route: Box<Route>,
+    },
+}
+
+pub struct Decision {
+    pub route: Route,
+    pub reason: String,
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.route {
+            Route::Command(command) => write!(f, "route: {} command", command.name())?,
+            Route::Prompt => write!(f, "route: prompt")?,
+            Route::Rag(name) => write!(f, "route: RAG over {name}")?,
+            Route::Cag(corpus) => write!(f, "route: CAG over {corpus}")?,
+            Route::Template { .. } => write!(f, "route: prompt template")?,
+        }
+        write!(f, ", reason: {}", self.reason)
+    }
+}
+
+/// Classify prompts using rules first - command patterns, user commands from config, explicit
+/// flags and corpus names mentioned in prompt - then, if enabled in config, a short SLM
+/// classification request. SLM classification only selects read-only routes, so that a wrong
+/// guess cannot start or stop a server.
+pub struct Router {
+    classifier: Option<Rc<dyn SlmBackend>>,
+    commands: Vec<UserCommand>,
+    explain: bool,
+}
+
+impl Router {
+    const CLASSIFIER_SYSTEM: &'static str = "Classify the user request. Respond with exactly \
+    one line, without any other text, using one of the following forms:\n\
+    DEFINE <word> - user asks for the meaning of a single word\n\
+    CORPUS <name> - user asks about documents from one of the corpora: {corpora}\n\
+    QA - any other request";
+
+    pub fn new(backend: Rc<dyn SlmBackend>, explain: bool) -> Result<Self> {
+        trace!("Router::new(backend: Rc<dyn SlmBackend>, explain: bool) -> Result<Self>");
+        let config = config::get_config().router.clone().unwrap_or_default();
+        let classifier = match (config.classify, config.backend) {
+            (false, _) => None,
+            (true, Some(name)) => Some(slm::backend(Some(&name))?),
+            (true, None) => Some(backend),
+        };
+        Ok(Self {
+            classifier,
+            commands: UserCommand::load(),
+            explain,
+        })
+    }
+
+    pub async fn route(&self, prompt: &str, context: Option<&str>, cag: Option<&str>) -> Decision {
+        trace!(
+            "Router::route(&self, prompt: &str, context: Option<&str>, cag: Option<&str>) -> Decision"
+        );
+        let decision = match self.rules(prompt, context, cag, corpora()) {
+            Some(decision) => decision,
+            None => match self.classify(prompt).await {
+                Ok(Some(decision)) => decision,
+                Ok(None) => Decision {
+                    route: Route::Prompt,
+                    reason: "no rule matched".to_string(),
+                },
+                Err(e) => Decision {
+                    route: Route::Prompt,
+                    reason: format!("SLM classification failed: {e}"),
+                },
+            },
+        };
+
+        info!("{decision}");
+        if self.explain {
+            eprintln!("{decision}");
+        }
+        decision
+    }
+
+    /// Rule based route: command patterns first, built-in then user commands, so that they
+    /// keep working with a corpus selected, then explicit flags and corpus names mentioned in
+    /// prompt.
+    fn rules(
+        &self,
+        prompt: &str,
+        context: Option<&str>,
+        cag: Option<&str>,
+        corpora: Vec<Corpus>,
+    ) -> Option<Decision> {
+        if let Ok(command) = prompt.parse::<Command>() {
+            let reason = format!("prompt matches {} command pattern", command.name());
+            return Some(Decision {
+                route: Route::Command(command),
+                reason,
+            });
+        }
+        for command in &self.commands {
+            if let Some(route) = command.route(prompt) {
+                return Some(Decision {
+                    route,
+                    reason: format!("prompt matches user command {}", command.name()),
+                });
+            }
+        }
+        if let Some(corpus) = cag {
+            return Some(Decision {
+                route: Route::Cag(corpus.to_string()),
+                reason: "CAG corpus selected by user".to_string(),
+            });
+        }
+        if let Some(context) = context {
+            return Some(Decision {
+                route: Route::Rag(context.to_string()),
+                reason: "RAG context selected by user".to_string(),
+            });
+        }
+
+        for corpus in corpora {
+            let Ok(regex) = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(corpus.name())))
+            else {
+                continue;
+            };
+            if regex.is_match(prompt) {
+                return Some(Decision {
+                    reason: format!("prompt mentions corpus {}", corpus.name()),
+                    route: corpus.into_route(),
+                });
+            }
+        }
+        None
+    }
+
+    async fn classify(&self, prompt: &str) -> Result<Option<Decision>> {
+        trace!("Router::classify(&self, prompt: &str) -> Result<Option<Decision>>");
+        let Some(classifier) = &self.classifier else {
+            return Ok(None);
+        };
+
+        let corpora = corpora();
+        let names: Vec<&str> = corpora.iter().map(Corpus::name).collect();
+        let system = Self::CLASSIFIER_SYSTEM.replace("{corpora}", &names.join(", "));
+        let mut request = SlmRequest::new(prompt);
+        request.set_system(&system);
+
+        let reply = classifier.complete(request).await?;
+        let line = reply.lines().map(str::trim).find(|line| !line.is_empty());
+        debug!("SLM classification: {line:?}");
+
+        let Some(line) = line else {
+            return Ok(None);
+        };
+        let mut parts = line.split_whitespace();
+        let label = parts.next().unwrap_or_default().to_uppercase();
+        let argument = parts.collect::<Vec<_>>().join(" ");
+        let route = match label.as_str() {
+            "DEFINE" => format!("define {argument}")
+                .parse()
+                .ok()
+                .map(Route::Command),
+            "CORPUS" => corpora
+                .into_iter()
+                .find(|corpus| corpus.name() == argument)
+                .map(Corpus::into_route),
+            "QA" => Some(Route::Prompt),
+            _ => {
+                warn!("unexpected SLM classification: {line}");
+                None
+            }
+        };
+        Ok(route.map(|route| Decision {
+            route,
+            reason: format!("SLM classified prompt as {line}"),
+        }))
+    }
+}
+
+/// Known corpus: a local RAG index or a CAG corpus configured by name.
+enum Corpus {
+    Index(String),
+    Documents(String),
+}
+
+impl Corpus {
+    fn name(&self) -> &str {
+        match self {
+            Corpus::Index(name) | Corpus::Documents(name) => name,
+        }
+    }
+
+    fn into_route(self) -> Route {
+        match self {
+            Corpus::Index(name) => Route::Rag(name),
+            Corpus::Documents(name) => Route::Cag(name),
+        }
+    }
+}
+
+fn corpora() -> Vec<Corpus> {
+    let mut corpora: Vec<Corpus> = Index::names()
+        .unwrap_or_default()
+        .into_iter()
+        .map(Corpus::Index)
+        .collect();
+    let config = config::get_config();
+    corpora.extend(config.corpora.keys().cloned().map(Corpus::Documents));
+    corpora
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config;
+    use crate::router::{Corpus, Route, Router};
+
+    #[test]
+    fn rules() {
+        config::init_test_config();
+        let router = Router {
+            classifier: None,
+            commands: Vec::new(),
+            explain: false,
+        };
+        let route = |prompt: &str, context: Option<&str>, cag: Option<&str>| {
+            let corpora = vec![
+                Corpus::Index("manual".to_string()),
+                Corpus::Documents("notes".to_string()),
+            ];
+            router
+                .rules(prompt, context, cag, corpora)
+                .map(|decision| decision.route)
+        };
+
+        for prompt in ["server status", "define casă", "session list"] {
+            assert!(matches!(
+                route(prompt, Some("docs"), None),
+                Some(Route::Command(_))
+            ));
+            assert!(matches!(
+                route(prompt, None, Some("notes")),
+                Some(Route::Command(_))
+            ));
+        }
+        assert!(matches!(
+            route("what is new", Some("docs"), Some("notes")),
+            Some(Route::Cag(corpus)) if corpus == "notes"
+        ));
+        assert!(matches!(
+            route("what is new", Some("docs"), None),
+            Some(Route::Rag(context)) if context == "docs"
+        ));
+        assert!(matches!(
+            route("what does the Manual say", None, None),
+            Some(Route::Rag(index)) if index == "manual"
+        ));
+        assert!(matches!(
+            route("summarize my notes", None, None),
+            Some(Route::Cag(corpus)) if corpus == "notes"
+        ));
+        assert!(route("what about manuals", None, None).is_none());
+    }
+}
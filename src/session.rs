@@ -0,0 +1,204 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+use log::{debug, trace};
+
+use crate::{
+    error::{AppError, Result},
+    slm::{Message, Role},
+    util::dirs,
+};
+
+/// Named conversation persisted on disk as JSON lines, one message per line, so that every
+/// exchange is appended without rewriting the file.
+pub struct Session {
+    name: String,
+    path: PathBuf,
+    history: Vec<Message>,
+}
+
+impl Session {
+    const SESSIONS_DIR: &'static str = "sessions";
+    const EXTENSION: &'static str = "jsonl";
+
+    /// Load session history, starting an empty session if there is none with given name.
+    pub fn open(name: &str) -> Result<Self> {
+        trace!("Session::open(name: &str) -> Result<Self>");
+        let path = Self::path(name)?;
+        let mut history = Vec::new();
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    history.push(serde_json::from_str(&line)?);
+                }
+            }
+        }
+        debug!("session {name}: {} messages", history.len());
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            history,
+        })
+    }
+
+    /// Load an existing session, failing if not found.
+    pub fn load(name: &str) -> Result<Self> {
+        trace!("Session::load(name: &str) -> Result<Self>");
+        if !Self::path(name)?.exists() {
+            return Err(AppError::Session(format!("{name} not found")));
+        }
+        Self::open(name)
+    }
+
+    pub fn list() -> Result<Vec<Session>> {
+        trace!("Session::list() -> Result<Vec<Session>>");
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dirs::data_dir(Self::SESSIONS_DIR)?)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == Self::EXTENSION)
+                && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        names.iter().map(|name| Self::open(name)).collect()
+    }
+
+    pub fn delete(name: &str) -> Result<()> {
+        trace!("Session::delete(name: &str) -> Result<()>");
+        let path = Self::path(name)?;
+        if !path.exists() {
+            return Err(AppError::Session(format!("{name} not found")));
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn history(&self) -> &[Message] {
+        &self.history
+    }
+
+    /// Record an exchange both in memory and on disk.
+    pub fn append(&mut self, messages: &[Message]) -> Result<()> {
+        trace!("Session::append(&mut self, messages: &[Message]) -> Result<()>");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        for message in messages {
+            writeln!(file, "{}", serde_json::to_string(message)?)?;
+        }
+        self.history.extend_from_slice(messages);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        trace!("Session::clear(&mut self) -> Result<()>");
+        File::create(&self.path)?;
+        self.history.clear();
+        Ok(())
+    }
+
+    fn path(name: &str) -> Result<PathBuf> {
+        dirs::data_file(Self::SESSIONS_DIR, name, Self::EXTENSION)
+    }
+}
+
+/// Render conversation as Markdown, one section per message.
+pub fn markdown(history: &[Message]) -> String {
+    let mut markdown = String::new();
+    for message in history {
+        let heading = match message.role {
+            Role::System => "System",
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::Tool => "Tool",
+        };
+        markdown.push_str(&format!("## {heading}\n\n{}\n\n", message.content.trim()));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+
+    use crate::session::{self, Session};
+    use crate::slm::{Message, Role};
+
+    fn contents(session: &Session) -> Vec<(Role, String)> {
+        session
+            .history()
+            .iter()
+            .map(|message| (message.role, message.content.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let data_home = env::temp_dir().join(format!("jarvis-session-{}", std::process::id()));
+        // SAFETY: the only test changing the environment; data files of other tests are not
+        // resolved through XDG_DATA_HOME
+        unsafe { env::set_var("XDG_DATA_HOME", &data_home) };
+
+        let mut session = Session::open("notes").unwrap();
+        assert!(session.history().is_empty());
+        assert!(Session::load("notes").is_err());
+        let exchange = [
+            Message::new(Role::User, "hello"),
+            Message::new(Role::Assistant, "line\nwith \"quotes\""),
+        ];
+        session.append(&exchange).unwrap();
+        session
+            .append(&[Message::new(Role::User, "again")])
+            .unwrap();
+
+        let loaded = Session::load("notes").unwrap();
+        assert_eq!(
+            contents(&loaded),
+            [
+                (Role::User, "hello".to_string()),
+                (Role::Assistant, "line\nwith \"quotes\"".to_string()),
+                (Role::User, "again".to_string()),
+            ]
+        );
+        Session::open("draft").unwrap().append(&exchange).unwrap();
+        let names: Vec<String> = Session::list()
+            .unwrap()
+            .iter()
+            .map(|session| session.name().to_string())
+            .collect();
+        assert_eq!(names, ["draft", "notes"]);
+
+        session.clear().unwrap();
+        assert!(session.history().is_empty());
+        assert!(Session::load("notes").unwrap().history().is_empty());
+        Session::delete("draft").unwrap();
+        assert!(Session::delete("draft").is_err());
+        assert!(Session::open("../notes").is_err());
+
+        fs::remove_dir_all(&data_home).unwrap();
+    }
+
+    #[test]
+    fn markdown() {
+        let history = [
+            Message::new(Role::System, "Be brief"),
+            Message::new(Role::User, "  hello\n"),
+            Message::new(Role::Assistant, "Hi!"),
+        ];
+        assert_eq!(
+            session::markdown(&history),
+            "## System\n\nBe brief\n\n## User\n\nhello\n\n## Assistant\n\nHi!\n\n"
+        );
+    }
+}
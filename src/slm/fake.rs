@@ -0,0 +1,79 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use futures::stream;
+
+use crate::{
+    agent::AgentStream,
+    error::{AppError, Result},
+    slm::{Message, Reply, SlmBackend, SlmRequest, ToolDefinition},
+};
+
+/// In-memory backend for tests: records received requests and replies with canned chunks, or
+/// with canned complete replies when used for tool calling.
+pub struct FakeBackend {
+    chunks: Vec<String>,
+    replies: RefCell<VecDeque<Reply>>,
+    requests: RefCell<Vec<SlmRequest>>,
+}
+
+impl FakeBackend {
+    pub fn new(chunks: &[&str]) -> Self {
+        Self {
+            chunks: chunks.iter().map(|chunk| chunk.to_string()).collect(),
+            replies: RefCell::new(VecDeque::new()),
+            requests: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn with_replies(replies: Vec<Reply>) -> Self {
+        Self {
+            chunks: Vec::new(),
+            replies: RefCell::new(replies.into()),
+            requests: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn systems(&self) -> Vec<Option<String>> {
+        let requests = self.requests.borrow();
+        requests
+            .iter()
+            .map(|request| request.system.clone())
+            .collect()
+    }
+
+    /// Turns following the prompt, for every received request.
+    pub fn turns(&self) -> Vec<Vec<Message>> {
+        let requests = self.requests.borrow();
+        requests
+            .iter()
+            .map(|request| request.turns.clone())
+            .collect()
+    }
+}
+
+#[async_trait(?Send)]
+impl SlmBackend for FakeBackend {
+    async fn stream(&self, request: SlmRequest) -> AgentStream {
+        self.requests.borrow_mut().push(request);
+        let chunks: Vec<_> = self.chunks.iter().cloned().map(Ok).collect();
+        Box::pin(stream::iter(chunks))
+    }
+
+    fn url(&self) -> &str {
+        "fake:"
+    }
+
+    fn supports_tools(&self) -> bool {
+        !self.replies.borrow().is_empty()
+    }
+
+    async fn chat(&self, request: &SlmRequest, _tools: &[ToolDefinition]) -> Result<Reply> {
+        self.requests.borrow_mut().push(request.clone());
+        self.replies
+            .borrow_mut()
+            .pop_front()
+            .ok_or_else(|| AppError::Fatal("no canned reply".to_string()))
+    }
+}
@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use log::trace;
+use reqwest::Client;
+
+use crate::{
+    agent::AgentStream,
+    config::BackendConfig,
+    error::Result,
+    slm::{SlmBackend, SlmRequest, text_stream},
+};
+
+const SLM_URL: &str = "http://jarvis.local:1964/";
+
+/// Jarvis SLM service accepting `SlmRequest` JSON and streaming raw text back.
+pub struct JarvisBackend {
+    slm_url: String,
+    http_client: Client,
+}
+
+impl JarvisBackend {
+    pub fn build(config: &BackendConfig) -> Result<Box<dyn SlmBackend>> {
+        trace!("JarvisBackend::build(config: &BackendConfig) -> Result<Box<dyn SlmBackend>>");
+        let slm_url = config.url.as_deref().unwrap_or(SLM_URL).to_string();
+        let http_client = Client::builder().build()?;
+        Ok(Box::new(Self {
+            slm_url,
+            http_client,
+        }))
+    }
+}
+
+#[async_trait(?Send)]
+impl SlmBackend for JarvisBackend {
+    async fn stream(&self, request: SlmRequest) -> AgentStream {
+        trace!("JarvisBackend::stream(&self, request: SlmRequest) -> AgentStream");
+
+        let response = self
+            .http_client
+            .post(&self.slm_url)
+            .json(&request)
+            .send()
+            .await;
+        text_stream(response)
+    }
+
+    fn url(&self) -> &str {
+        &self.slm_url
+    }
+}
@@ -0,0 +1,530 @@
+mod jarvis;
+mod ollama;
+mod openai;
+
+#[cfg(test)]
+pub mod fake;
+
+use std::rc::Rc;
+
+use crate::{
+    agent::AgentStream,
+    attachment::Attachment,
+    config::{self, BackendConfig},
+    error::{AppError, Result},
+};
+use async_stream::stream;
+use async_trait::async_trait;
+use futures::StreamExt;
+use lazy_static::lazy_static;
+use log::{debug, error, trace};
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize)]
+pub struct SlmRequest {
+    prompt: String,
+    system: Option<String>,
+    profile: Option<String>,
+    settings: Option<String>,
+    context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    documents: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    history: Vec<Message>,
+    /// Turns following the prompt in current exchange, that is, tool calls and their results.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    turns: Vec<Message>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+/// Conversation turn, serialized with the role and content names used by chat APIs. Assistant
+/// turns may request tool calls and tool turns carry the result for the call with given id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl Message {
+    pub fn new(role: Role, content: &str) -> Self {
+        Self {
+            role,
+            content: content.to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    pub fn tool_result(call: &ToolCall, content: &str) -> Self {
+        Self {
+            role: Role::Tool,
+            content: content.to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: Some(call.id.clone()),
+        }
+    }
+}
+
+/// Function the model may ask to call, described by a JSON schema of its parameters.
+#[derive(Debug, Clone)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+impl ToolDefinition {
+    /// Function tool declaration, in the shape shared by OpenAI and Ollama APIs.
+    pub fn function(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// Complete, not streamed, assistant reply: final text or tool calls requests.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub content: String,
+    pub tool_calls: Vec<ToolCall>,
+}
+
+impl SlmRequest {
+    pub fn new(prompt: &str) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            system: None,
+            profile: None,
+            settings: None,
+            context: None,
+            documents: None,
+            cache_id: None,
+            history: Vec::new(),
+            turns: Vec::new(),
+        }
+    }
+
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    pub fn system(&self) -> Option<&str> {
+        self.system.as_deref()
+    }
+
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = prompt.to_string();
+    }
+
+    pub fn set_system(&mut self, system: &str) {
+        self.system = Some(system.to_string());
+    }
+
+    pub fn set_profile(&mut self, profile: &str) {
+        self.profile = Some(profile.to_string());
+    }
+
+    pub fn set_settings(&mut self, settings: &str) {
+        self.settings = Some(settings.to_string());
+    }
+
+    pub fn set_context(&mut self, context: &str) {
+        self.context = Some(context.to_string());
+    }
+
+    /// Preloaded documents for cache-augmented generation; they are placed in the stable
+    /// prefix of the request so the backend can reuse its prompt cache across questions.
+    pub fn set_documents(&mut self, documents: &str, cache_id: &str) {
+        self.documents = Some(documents.to_string());
+        self.cache_id = Some(cache_id.to_string());
+    }
+
+    pub fn cache_id(&self) -> Option<&str> {
+        self.cache_id.as_deref()
+    }
+
+    /// Append attachment content to the prompt, under a header with attachment name.
+    pub fn add_attachment(&mut self, attachment: &Attachment) {
+        self.prompt.push_str(&format!(
+            "\n\n--- {} ---\n{}",
+            attachment.name(),
+            attachment.content().trim_end()
+        ));
+    }
+
+    /// Prior conversation turns, oldest first, sent before the prompt.
+    pub fn set_history(&mut self, history: &[Message]) {
+        self.history = history.to_vec();
+    }
+
+    pub fn push_turn(&mut self, message: Message) {
+        self.turns.push(message);
+    }
+
+    /// Chat messages for backends with a messages API: system message, history, prompt and
+    /// current exchange turns.
+    fn messages(&self) -> Vec<Message> {
+        let mut messages = Vec::new();
+        if let Some(system) = self.system_message() {
+            messages.push(Message::new(Role::System, &system));
+        }
+        messages.extend(self.history.iter().cloned());
+        messages.push(Message::new(Role::User, &self.prompt));
+        messages.extend(self.turns.iter().cloned());
+        messages
+    }
+
+    /// Flatten system role, user profile, system settings, documents and context into a single
+    /// system message, for backends that have no dedicated fields for them.
+    fn system_message(&self) -> Option<String> {
+        let sections: Vec<String> = [
+            self.system.clone(),
+            self.profile
+                .as_ref()
+                .map(|profile| format!("User profile:\n{profile}")),
+            self.settings
+                .as_ref()
+                .map(|settings| format!("System settings:\n{settings}")),
+            self.documents
+                .as_ref()
+                .map(|documents| format!("Documents:\n{documents}")),
+            self.context
+                .as_ref()
+                .map(|context| format!("Context:\n{context}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        match sections.is_empty() {
+            true => None,
+            false => Some(sections.join("\n\n")),
+        }
+    }
+}
+
+/// Runtime information exposed by SLM service; fields are empty when not available.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServiceStatus {
+    pub models: Vec<String>,
+    /// Video memory used and available, in bytes.
+    pub vram_used: Option<u64>,
+    pub vram_total: Option<u64>,
+    /// Requests being processed or waiting.
+    pub queue: Option<usize>,
+    /// Service uptime, in seconds.
+    pub uptime: Option<u64>,
+}
+
+/// Language model service able to answer a request with a stream of text chunks.
+#[async_trait(?Send)]
+pub trait SlmBackend {
+    async fn stream(&self, request: SlmRequest) -> AgentStream;
+
+    /// Whole reply to a request, for answers that are parsed or shown at once.
+    async fn complete(&self, request: SlmRequest) -> Result<String> {
+        let mut reply = String::new();
+        let mut stream = self.stream(request).await;
+        while let Some(chunk) = stream.next().await {
+            reply.push_str(&chunk?);
+        }
+        Ok(reply)
+    }
+
+    /// Service base URL.
+    fn url(&self) -> &str;
+
+    /// Loaded models and resource usage, as far as the service API exposes them.
+    async fn status(&self) -> Result<ServiceStatus> {
+        Ok(ServiceStatus::default())
+    }
+
+    /// Compute embedding vectors for given texts, in the same order; not all backends support it.
+    async fn embed(&self, _inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        Err(AppError::Unsupported("embeddings".to_string()))
+    }
+
+    /// Whether tools are advertised to this backend; enabled by configuration since function
+    /// calling depends on the loaded model.
+    fn supports_tools(&self) -> bool {
+        false
+    }
+
+    /// Complete, not streamed, reply to a request advertising given tools.
+    async fn chat(&self, _request: &SlmRequest, _tools: &[ToolDefinition]) -> Result<Reply> {
+        Err(AppError::Unsupported("tool calling".to_string()))
+    }
+}
+
+pub type BackendBuilder = fn(&BackendConfig) -> Result<Box<dyn SlmBackend>>;
+
+lazy_static! {
+    static ref BACKENDS: Vec<(&'static str, BackendBuilder)> = vec![
+        ("jarvis", jarvis::JarvisBackend::build),
+        ("openai", openai::OpenAiBackend::build),
+        ("ollama", ollama::OllamaBackend::build),
+    ];
+}
+
+/// Create the backend configured under given name, or the default one if name is missing.
+pub fn backend(name: Option<&str>) -> Result<Rc<dyn SlmBackend>> {
+    trace!("slm::backend(name: Option<&str>) -> Result<Rc<dyn SlmBackend>>");
+    let config = config::get_config().backend(name)?;
+    debug!("backend config: {config:?}");
+    for (kind, builder) in BACKENDS.iter() {
+        if *kind == config.kind {
+            return Ok(Rc::from(builder(&config)?));
+        }
+    }
+    Err(AppError::UnknownBackend(config.kind))
+}
+
+/// Outcome of decoding one line of a line oriented streaming protocol.
+#[derive(Debug, PartialEq)]
+enum LineEvent {
+    Text(String),
+    Done,
+    Ignore,
+}
+
+/// Decode a streamed HTTP response as text, yielded as it arrives. UTF-8 sequences split across
+/// network chunks are reassembled; a failed request, an error status or invalid text is
+/// reported as an error that ends the stream.
+fn text_stream(response: reqwest::Result<Response>) -> AgentStream {
+    Box::pin(stream! {
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                yield Err(AppError::Reqwest(e));
+                return;
+            }
+        };
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            error!("request failed with status: {status}");
+            yield Err(AppError::Fatal(format!("SLM response status {status}: {text}")));
+            return;
+        }
+
+        let mut buffer = Vec::<u8>::new();
+        let mut bytes = response.bytes_stream();
+        while let Some(chunk) = bytes.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    yield Err(AppError::Reqwest(e));
+                    return;
+                }
+            };
+            buffer.extend_from_slice(&chunk);
+            match take_utf8(&mut buffer) {
+                Ok(text) if text.is_empty() => {}
+                Ok(text) => yield Ok(text),
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+        if !buffer.is_empty() {
+            yield Err(AppError::Fatal("SLM response ends inside a UTF-8 sequence".to_string()));
+        }
+    })
+}
+
+/// Decode a streamed HTTP response line by line, e.g. server-sent events or NDJSON, mapping
+/// each line to agent text with the given decoder. Lines split across network chunks are
+/// reassembled before decoding, see [text_stream] for the text itself.
+fn line_stream(
+    response: reqwest::Result<Response>,
+    decode: fn(&str) -> Result<LineEvent>,
+) -> AgentStream {
+    let mut text = text_stream(response);
+    Box::pin(stream! {
+        let mut buffer = String::new();
+        loop {
+            let line = match buffer.find('\n') {
+                Some(position) => buffer.drain(..=position).collect::<String>(),
+                None => match text.next().await {
+                    Some(Ok(chunk)) => {
+                        buffer.push_str(&chunk);
+                        continue;
+                    }
+                    Some(Err(e)) => {
+                        yield Err(e);
+                        return;
+                    }
+                    None if buffer.is_empty() => return,
+                    None => std::mem::take(&mut buffer),
+                },
+            };
+            match decode(line.trim()) {
+                Ok(LineEvent::Text(text)) => yield Ok(text),
+                Ok(LineEvent::Done) => return,
+                Ok(LineEvent::Ignore) => {}
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+    })
+}
+
+/// Take the text decoded from buffer, leaving there a trailing UTF-8 sequence split across
+/// network chunks, to be completed by the next chunk.
+fn take_utf8(buffer: &mut Vec<u8>) -> Result<String> {
+    let valid = match std::str::from_utf8(buffer) {
+        Ok(text) => text.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(e) => {
+            return Err(AppError::Fatal(format!(
+                "invalid UTF-8 in SLM response: {e}"
+            )));
+        }
+    };
+    let bytes: Vec<u8> = buffer.drain(..valid).collect();
+    // validated above
+    Ok(String::from_utf8(bytes).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        config,
+        slm::{self, LineEvent, SlmRequest, line_stream, take_utf8, text_stream},
+    };
+    use futures::StreamExt;
+    use tokio::{
+        io::{self, AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn split_utf8() {
+        let text = "țară".as_bytes();
+        let mut buffer = text[..1].to_vec();
+        assert_eq!(take_utf8(&mut buffer).unwrap(), "");
+        buffer.extend_from_slice(&text[1..5]);
+        assert_eq!(take_utf8(&mut buffer).unwrap(), "țar");
+        assert_eq!(buffer, text[4..5]);
+        buffer.extend_from_slice(&text[5..]);
+        assert_eq!(take_utf8(&mut buffer).unwrap(), "ă");
+        assert!(buffer.is_empty());
+
+        let mut buffer = vec![b'a', 0xFF, b'b'];
+        assert!(take_utf8(&mut buffer).is_err());
+    }
+
+    /// Local HTTP server sending given body parts as separate network writes.
+    async fn serve(status: u16, parts: Vec<Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            let head = format!("HTTP/1.1 {status} X\r\nConnection: close\r\n\r\n");
+            stream.write_all(head.as_bytes()).await.unwrap();
+            for part in parts {
+                stream.write_all(&part).await.unwrap();
+                stream.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn streams() {
+        let body = "  indented țară\nnext line".as_bytes();
+        let parts = vec![body[..14].to_vec(), body[14..].to_vec()];
+        let url = serve(200, parts.clone()).await;
+        let text = text_stream(reqwest::get(&url).await);
+        let chunks: Vec<String> = text.map(|chunk| chunk.unwrap()).collect().await;
+        assert_eq!(chunks.concat(), "  indented țară\nnext line");
+
+        let url = serve(200, parts).await;
+        let lines = line_stream(reqwest::get(&url).await, |line| {
+            Ok(LineEvent::Text(format!("[{line}]")))
+        });
+        let lines: Vec<String> = lines.map(|line| line.unwrap()).collect().await;
+        assert_eq!(lines, ["[indented țară]", "[next line]"]);
+
+        let url = serve(503, vec![b"loading".to_vec()]).await;
+        let results: Vec<_> = text_stream(reqwest::get(&url).await).collect().await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "Unrecoverable error on SLM response status 503 Service Unavailable: loading"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a running SLM service"]
+    async fn prompt() {
+        let _ = config::init_config("config.yml");
+        let prompt = "how to eprint to stdout on rust";
+        let request = SlmRequest::new(prompt);
+
+        let backend = slm::backend(None).unwrap();
+        let mut stream = backend.stream(request).await;
+        while let Some(chunk) = stream.next().await {
+            print!("{}", chunk.unwrap());
+            io::stdout().flush().await.unwrap();
+        }
+        println!();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a running SLM service"]
+    async fn query() {
+        let _ = config::init_config("config.yml");
+        let context = "context";
+        let prompt = "please list all acronyms";
+        let mut request = SlmRequest::new(prompt);
+        request.set_context(context);
+
+        let backend = slm::backend(None).unwrap();
+        let mut stream = backend.stream(request).await;
+        while let Some(chunk) = stream.next().await {
+            print!("{}", chunk.unwrap());
+            io::stdout().flush().await.unwrap();
+        }
+        println!();
+    }
+}
@@ -0,0 +1,352 @@
+use async_trait::async_trait;
+use futures::stream;
+use log::trace;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    agent::AgentStream,
+    config::{BackendConfig, KeepAlive, OllamaApi, OllamaConfig},
+    error::{AppError, Result},
+    slm::{
+        LineEvent, Message, Reply, Role, ServiceStatus, SlmBackend, SlmRequest, ToolCall,
+        ToolDefinition, line_stream,
+    },
+};
+
+const OLLAMA_URL: &str = "http://localhost:11434/";
+/// Keep alive used for cached prefix requests when not configured, so that the model, together
+/// with its prompt cache, stays loaded between questions.
+const CACHE_KEEP_ALIVE: &str = "30m";
+
+/// Ollama native `/api/chat` and `/api/generate` endpoints streaming NDJSON.
+pub struct OllamaBackend {
+    slm_url: String,
+    model: Option<String>,
+    embedding_model: Option<String>,
+    ollama: OllamaConfig,
+    tools: bool,
+    http_client: Client,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<Message>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<Options>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<KeepAlive>,
+}
+
+/// Not streamed `/api/chat` request advertising tools; tool calls have no ids in Ollama API.
+#[derive(Serialize)]
+struct ToolsRequest<'a> {
+    model: &'a str,
+    messages: Vec<Value>,
+    tools: Vec<Value>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<Options>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<KeepAlive>,
+}
+
+#[derive(Deserialize)]
+struct ToolsResponse {
+    message: ToolsMessage,
+}
+
+#[derive(Deserialize)]
+struct ToolsMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<WireToolCall>,
+}
+
+#[derive(Deserialize)]
+struct WireToolCall {
+    function: WireFunction,
+}
+
+#[derive(Deserialize)]
+struct WireFunction {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Serialize)]
+struct GenerateRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<Options>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<KeepAlive>,
+}
+
+#[derive(Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<KeepAlive>,
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+struct PsResponse {
+    models: Vec<PsModel>,
+}
+
+#[derive(Deserialize)]
+struct PsModel {
+    name: String,
+    #[serde(default)]
+    size_vram: u64,
+}
+
+#[derive(Deserialize)]
+struct ChunkMessage {
+    content: String,
+}
+
+#[derive(Serialize)]
+struct Options {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+}
+
+/// One NDJSON line from either endpoint: `/api/chat` fills `message`, `/api/generate` fills
+/// `response` and failures mid-stream come as `error`.
+#[derive(Deserialize)]
+struct Chunk {
+    message: Option<ChunkMessage>,
+    response: Option<String>,
+    error: Option<String>,
+    #[serde(default)]
+    done: bool,
+}
+
+impl OllamaBackend {
+    pub fn build(config: &BackendConfig) -> Result<Box<dyn SlmBackend>> {
+        trace!("OllamaBackend::build(config: &BackendConfig) -> Result<Box<dyn SlmBackend>>");
+        Ok(Box::new(Self {
+            slm_url: config.url.as_deref().unwrap_or(OLLAMA_URL).to_string(),
+            model: config.model.clone(),
+            embedding_model: config.embedding_model.clone(),
+            ollama: config.ollama.clone().unwrap_or_default(),
+            tools: config.tools,
+            http_client: Client::builder().build()?,
+        }))
+    }
+}
+
+#[async_trait(?Send)]
+impl SlmBackend for OllamaBackend {
+    async fn stream(&self, request: SlmRequest) -> AgentStream {
+        trace!("OllamaBackend::stream(&self, request: SlmRequest) -> AgentStream");
+        let Some(model) = self.model.as_deref() else {
+            let error = AppError::Fatal("Ollama backend requires a model".to_string());
+            return Box::pin(stream::iter([Err(error)]));
+        };
+
+        let options = self.options();
+        let keep_alive = self.keep_alive(&request);
+        let base_url = self.slm_url.trim_end_matches('/');
+
+        let builder = match self.ollama.api.unwrap_or_default() {
+            OllamaApi::Chat => {
+                let body = ChatRequest {
+                    model,
+                    messages: request.messages(),
+                    stream: true,
+                    options,
+                    keep_alive,
+                };
+                self.http_client
+                    .post(format!("{base_url}/api/chat"))
+                    .json(&body)
+            }
+            OllamaApi::Generate => {
+                let body = GenerateRequest {
+                    model,
+                    prompt: &request.prompt,
+                    system: request.system_message(),
+                    stream: true,
+                    options,
+                    keep_alive,
+                };
+                self.http_client
+                    .post(format!("{base_url}/api/generate"))
+                    .json(&body)
+            }
+        };
+        line_stream(builder.send().await, parse_line)
+    }
+
+    fn url(&self) -> &str {
+        &self.slm_url
+    }
+
+    /// Models currently loaded in memory, from `/api/ps`, with their video memory usage.
+    async fn status(&self) -> Result<ServiceStatus> {
+        trace!("OllamaBackend::status(&self) -> Result<ServiceStatus>");
+        let url = format!("{}/api/ps", self.slm_url.trim_end_matches('/'));
+        let response = self.http_client.get(url).send().await?;
+        let response: PsResponse = response.error_for_status()?.json().await?;
+        Ok(ServiceStatus {
+            vram_used: Some(response.models.iter().map(|model| model.size_vram).sum()),
+            models: response
+                .models
+                .into_iter()
+                .map(|model| model.name)
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    fn supports_tools(&self) -> bool {
+        self.tools
+    }
+
+    async fn chat(&self, request: &SlmRequest, tools: &[ToolDefinition]) -> Result<Reply> {
+        trace!(
+            "OllamaBackend::chat(&self, request: &SlmRequest, tools: &[ToolDefinition]) -> Result<Reply>"
+        );
+        let Some(model) = self.model.as_deref() else {
+            return Err(AppError::Fatal(
+                "Ollama backend requires a model".to_string(),
+            ));
+        };
+        let body = ToolsRequest {
+            model,
+            messages: request.messages().iter().map(wire_message).collect(),
+            tools: tools.iter().map(ToolDefinition::function).collect(),
+            stream: false,
+            options: self.options(),
+            keep_alive: self.keep_alive(request),
+        };
+        let url = format!("{}/api/chat", self.slm_url.trim_end_matches('/'));
+        let response = self.http_client.post(url).json(&body).send().await?;
+        let response: ToolsResponse = response.error_for_status()?.json().await?;
+        let tool_calls = (0..)
+            .zip(response.message.tool_calls)
+            .map(|(index, call)| ToolCall {
+                id: format!("call_{index}"),
+                name: call.function.name,
+                arguments: call.function.arguments,
+            })
+            .collect();
+        Ok(Reply {
+            content: response.message.content,
+            tool_calls,
+        })
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        trace!("OllamaBackend::embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>>");
+        let Some(model) = self.embedding_model.as_deref().or(self.model.as_deref()) else {
+            return Err(AppError::Fatal(
+                "Ollama backend requires a model".to_string(),
+            ));
+        };
+        let body = EmbedRequest {
+            model,
+            input: inputs,
+            keep_alive: self.ollama.keep_alive.clone(),
+        };
+        let url = format!("{}/api/embed", self.slm_url.trim_end_matches('/'));
+        let response = self.http_client.post(url).json(&body).send().await?;
+        let response: EmbedResponse = response.error_for_status()?.json().await?;
+        Ok(response.embeddings)
+    }
+}
+
+impl OllamaBackend {
+    fn options(&self) -> Option<Options> {
+        match (self.ollama.temperature, self.ollama.num_ctx) {
+            (None, None) => None,
+            (temperature, num_ctx) => Some(Options {
+                temperature,
+                num_ctx,
+            }),
+        }
+    }
+
+    fn keep_alive(&self, request: &SlmRequest) -> Option<KeepAlive> {
+        match (&self.ollama.keep_alive, request.cache_id()) {
+            (Some(keep_alive), _) => Some(keep_alive.clone()),
+            (None, Some(_)) => Some(KeepAlive::Duration(CACHE_KEEP_ALIVE.to_string())),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Chat message as expected by Ollama API, with tool call arguments as JSON object.
+fn wire_message(message: &Message) -> Value {
+    let mut value = json!({"role": message.role, "content": message.content});
+    if message.role == Role::Assistant && !message.tool_calls.is_empty() {
+        let calls: Vec<Value> = message
+            .tool_calls
+            .iter()
+            .map(|call| json!({"function": {"name": call.name, "arguments": call.arguments}}))
+            .collect();
+        value["tool_calls"] = Value::Array(calls);
+    }
+    value
+}
+
+fn parse_line(line: &str) -> Result<LineEvent> {
+    if line.is_empty() {
+        return Ok(LineEvent::Ignore);
+    }
+
+    let chunk: Chunk = serde_json::from_str(line)?;
+    if let Some(error) = chunk.error {
+        return Err(AppError::Fatal(format!("Ollama: {error}")));
+    }
+    let text = match (chunk.message, chunk.response) {
+        (Some(message), _) => message.content,
+        (None, Some(response)) => response,
+        (None, None) => String::new(),
+    };
+    match (text.is_empty(), chunk.done) {
+        (false, _) => Ok(LineEvent::Text(text)),
+        (true, true) => Ok(LineEvent::Done),
+        (true, false) => Ok(LineEvent::Ignore),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::slm::{LineEvent, ollama::parse_line};
+
+    #[test]
+    fn parse_chunks() {
+        let line =
+            r#"{"model":"llama3","message":{"role":"assistant","content":"Hi"},"done":false}"#;
+        assert_eq!(parse_line(line).unwrap(), LineEvent::Text("Hi".to_string()));
+        let line = r#"{"model":"llama3","response":" there","done":false}"#;
+        assert_eq!(
+            parse_line(line).unwrap(),
+            LineEvent::Text(" there".to_string())
+        );
+        let line = r#"{"model":"llama3","response":"","done":true,"eval_count":12}"#;
+        assert_eq!(parse_line(line).unwrap(), LineEvent::Done);
+        assert!(parse_line(r#"{"error":"model not found"}"#).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use async_trait::async_trait;
+use log::trace;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    agent::AgentStream,
+    config::BackendConfig,
+    error::{AppError, Result},
+    slm::{
+        LineEvent, Message, Reply, Role, ServiceStatus, SlmBackend, SlmRequest, ToolCall,
+        ToolDefinition, line_stream,
+    },
+};
+
+const OPENAI_URL: &str = "http://localhost:8080/v1/";
+
+/// OpenAI compatible `/v1/chat/completions` endpoint, e.g. llama.cpp server, vLLM or LM Studio.
+pub struct OpenAiBackend {
+    slm_url: String,
+    model: Option<String>,
+    embedding_model: Option<String>,
+    api_key: Option<String>,
+    tools: bool,
+    http_client: Client,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<&'a str>,
+    messages: Vec<Message>,
+    stream: bool,
+    /// llama.cpp server extension: keep the prompt KV cache so a shared prefix is not
+    /// evaluated again on next request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_prompt: Option<bool>,
+}
+
+/// Not streamed chat completion advertising tools; messages are already in wire format since
+/// tool calls arguments are sent as JSON strings.
+#[derive(Serialize)]
+struct ToolsRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<&'a str>,
+    messages: Vec<Value>,
+    tools: Vec<Value>,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct ToolsResponse {
+    choices: Vec<ToolsChoice>,
+}
+
+#[derive(Deserialize)]
+struct ToolsChoice {
+    message: ToolsMessage,
+}
+
+#[derive(Deserialize)]
+struct ToolsMessage {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<WireToolCall>,
+}
+
+#[derive(Deserialize)]
+struct WireToolCall {
+    id: String,
+    function: WireFunction,
+}
+
+#[derive(Deserialize)]
+struct WireFunction {
+    name: String,
+    arguments: String,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<&'a str>,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<Embedding>,
+}
+
+#[derive(Deserialize)]
+struct Embedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct ModelsResponse {
+    data: Vec<Model>,
+}
+
+#[derive(Deserialize)]
+struct Model {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct ChatChunk {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    #[serde(default)]
+    delta: ChatDelta,
+}
+
+#[derive(Deserialize, Default)]
+struct ChatDelta {
+    content: Option<String>,
+}
+
+impl OpenAiBackend {
+    pub fn build(config: &BackendConfig) -> Result<Box<dyn SlmBackend>> {
+        trace!("OpenAiBackend::build(config: &BackendConfig) -> Result<Box<dyn SlmBackend>>");
+        Ok(Box::new(Self {
+            slm_url: config.url.as_deref().unwrap_or(OPENAI_URL).to_string(),
+            model: config.model.clone(),
+            embedding_model: config.embedding_model.clone(),
+            api_key: config.api_key.clone(),
+            tools: config.tools,
+            http_client: Client::builder().build()?,
+        }))
+    }
+}
+
+#[async_trait(?Send)]
+impl SlmBackend for OpenAiBackend {
+    async fn stream(&self, request: SlmRequest) -> AgentStream {
+        trace!("OpenAiBackend::stream(&self, request: SlmRequest) -> AgentStream");
+        let url = format!("{}/chat/completions", self.slm_url.trim_end_matches('/'));
+        let body = ChatRequest {
+            model: self.model.as_deref(),
+            messages: request.messages(),
+            stream: true,
+            cache_prompt: request.cache_id().map(|_| true),
+        };
+
+        let mut builder = self.http_client.post(&url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        line_stream(builder.send().await, parse_event)
+    }
+
+    fn url(&self) -> &str {
+        &self.slm_url
+    }
+
+    async fn status(&self) -> Result<ServiceStatus> {
+        trace!("OpenAiBackend::status(&self) -> Result<ServiceStatus>");
+        let url = format!("{}/models", self.slm_url.trim_end_matches('/'));
+        let mut builder = self.http_client.get(&url);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        let response: ModelsResponse = builder.send().await?.error_for_status()?.json().await?;
+        Ok(ServiceStatus {
+            models: response.data.into_iter().map(|model| model.id).collect(),
+            ..Default::default()
+        })
+    }
+
+    fn supports_tools(&self) -> bool {
+        self.tools
+    }
+
+    async fn chat(&self, request: &SlmRequest, tools: &[ToolDefinition]) -> Result<Reply> {
+        trace!(
+            "OpenAiBackend::chat(&self, request: &SlmRequest, tools: &[ToolDefinition]) -> Result<Reply>"
+        );
+        let url = format!("{}/chat/completions", self.slm_url.trim_end_matches('/'));
+        let body = ToolsRequest {
+            model: self.model.as_deref(),
+            messages: request.messages().iter().map(wire_message).collect(),
+            tools: tools.iter().map(ToolDefinition::function).collect(),
+            stream: false,
+        };
+
+        let mut builder = self.http_client.post(&url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        let response: ToolsResponse = builder.send().await?.error_for_status()?.json().await?;
+        let message = response
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::Fatal("SLM response has no choices".to_string()))?
+            .message;
+        let mut tool_calls = Vec::new();
+        for call in message.tool_calls {
+            tool_calls.push(ToolCall {
+                id: call.id,
+                name: call.function.name,
+                arguments: arguments(&call.function.arguments)?,
+            });
+        }
+        Ok(Reply {
+            content: message.content.unwrap_or_default(),
+            tool_calls,
+        })
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        trace!("OpenAiBackend::embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>>");
+        let url = format!("{}/embeddings", self.slm_url.trim_end_matches('/'));
+        let body = EmbeddingRequest {
+            model: self.embedding_model.as_deref().or(self.model.as_deref()),
+            input: inputs,
+        };
+
+        let mut builder = self.http_client.post(&url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        let response: EmbeddingResponse = builder.send().await?.error_for_status()?.json().await?;
+        let mut data = response.data;
+        data.sort_by_key(|embedding| embedding.index);
+        Ok(data
+            .into_iter()
+            .map(|embedding| embedding.embedding)
+            .collect())
+    }
+}
+
+/// Chat message as expected by OpenAI API, with tool call arguments encoded as JSON string.
+fn wire_message(message: &Message) -> Value {
+    let mut value = json!({"role": message.role, "content": message.content});
+    if message.role == Role::Assistant && !message.tool_calls.is_empty() {
+        let calls: Vec<Value> = message
+            .tool_calls
+            .iter()
+            .map(|call| {
+                json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": {"name": call.name, "arguments": call.arguments.to_string()},
+                })
+            })
+            .collect();
+        value["tool_calls"] = Value::Array(calls);
+    }
+    if let Some(id) = &message.tool_call_id {
+        value["tool_call_id"] = Value::String(id.clone());
+    }
+    value
+}
+
+/// Decode a single server-sent events line; only `data:` fields carry completion chunks.
+/// Tool call arguments; some servers send an empty string for tools without parameters.
+fn arguments(text: &str) -> Result<Value> {
+    match text.trim().is_empty() {
+        true => Ok(json!({})),
+        false => Ok(serde_json::from_str(text)?),
+    }
+}
+
+fn parse_event(line: &str) -> Result<LineEvent> {
+    let Some(data) = line.strip_prefix("data:") else {
+        return Ok(LineEvent::Ignore);
+    };
+    let data = data.trim();
+    if data == "[DONE]" {
+        return Ok(LineEvent::Done);
+    }
+
+    let chunk: ChatChunk = serde_json::from_str(data)?;
+    let text: String = chunk
+        .choices
+        .into_iter()
+        .filter_map(|choice| choice.delta.content)
+        .collect();
+    match text.is_empty() {
+        true => Ok(LineEvent::Ignore),
+        false => Ok(LineEvent::Text(text)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::slm::{
+        LineEvent,
+        openai::{arguments, parse_event},
+    };
+
+    #[test]
+    fn parse_delta() {
+        let line = r#"data: {"id":"1","choices":[{"index":0,"delta":{"content":"Hello"}}]}"#;
+        assert_eq!(
+            parse_event(line).unwrap(),
+            LineEvent::Text("Hello".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_control_lines() {
+        assert_eq!(parse_event("data: [DONE]").unwrap(), LineEvent::Done);
+        assert_eq!(parse_event(": keep-alive").unwrap(), LineEvent::Ignore);
+        assert_eq!(parse_event("").unwrap(), LineEvent::Ignore);
+        let line = r#"data: {"choices":[{"index":0,"delta":{"role":"assistant"}}]}"#;
+        assert_eq!(parse_event(line).unwrap(), LineEvent::Ignore);
+    }
+
+    #[test]
+    fn tool_arguments() {
+        assert_eq!(arguments("").unwrap(), json!({}));
+        assert_eq!(arguments(" \n").unwrap(), json!({}));
+        assert_eq!(
+            arguments(r#"{"word":"dor"}"#).unwrap(),
+            json!({"word": "dor"})
+        );
+        assert!(arguments("{").is_err());
+    }
+}
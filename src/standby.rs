@@ -0,0 +1,369 @@
+use std::{
+    cell::OnceCell,
+    env,
+    fs::{File, OpenOptions},
+    io::{Read, Seek, Write},
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    rc::Rc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use futures::stream;
+use log::{debug, info, trace, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agent::AgentStream,
+    config::{self, Server},
+    error::{AppError, Result},
+    lifecycle::{Lifecycle, State},
+    slm::{Reply, ServiceStatus, SlmBackend, SlmRequest, ToolDefinition},
+    util::dirs,
+};
+
+/// Server hosting the SLM backend, kept available on demand: it is started when a request finds
+/// the model not loaded and, if idle sleep is configured, shut down by a detached watcher
+/// process after given time without requests.
+pub struct Standby {
+    server: Server,
+}
+
+/// Backend whose server is made ready before every model request, so that agents, router
+/// classification and commands answered by SLM alike wait for a sleeping server to start.
+/// Status queries do not wake the server. The server is resolved on the first model request,
+/// so that commands not using the SLM work with an invalid server configuration.
+pub struct StandbyBackend {
+    backend: Rc<dyn SlmBackend>,
+    name: Option<String>,
+    standby: OnceCell<Option<Standby>>,
+}
+
+impl StandbyBackend {
+    /// Wrap backend with given name, none for the default one.
+    pub fn wrap(backend: Rc<dyn SlmBackend>, name: Option<&str>) -> Rc<dyn SlmBackend> {
+        Rc::new(Self {
+            backend,
+            name: name.map(str::to_string),
+            standby: OnceCell::new(),
+        })
+    }
+
+    /// Standby of backend server, none if backend has no server. Resolution errors are not
+    /// cached and are reported again on next request.
+    fn standby(&self) -> Result<Option<&Standby>> {
+        if self.standby.get().is_none() {
+            let standby = Standby::new(self.name.as_deref())?;
+            let _ = self.standby.set(standby);
+        }
+        Ok(self.standby.get().and_then(Option::as_ref))
+    }
+
+    async fn ready(&self) -> Result<()> {
+        if let Some(standby) = self.standby()? {
+            standby.ready().await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl SlmBackend for StandbyBackend {
+    async fn stream(&self, request: SlmRequest) -> AgentStream {
+        if let Err(e) = self.ready().await {
+            return Box::pin(stream::once(async { Err(e) }));
+        }
+        self.backend.stream(request).await
+    }
+
+    fn url(&self) -> &str {
+        self.backend.url()
+    }
+
+    async fn status(&self) -> Result<ServiceStatus> {
+        self.backend.status().await
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.ready().await?;
+        self.backend.embed(inputs).await
+    }
+
+    fn supports_tools(&self) -> bool {
+        self.backend.supports_tools()
+    }
+
+    async fn chat(&self, request: &SlmRequest, tools: &[ToolDefinition]) -> Result<Reply> {
+        self.ready().await?;
+        self.backend.chat(request, tools).await
+    }
+}
+
+/// Idle timer state shared by all CLI processes: last request time and the watcher process.
+/// State file is locked while read and while updated, see [IdleState::update].
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct IdleState {
+    last_request: u64,
+    watcher: Option<u32>,
+}
+
+/// Next step of the idle watcher, decided from idle state at a given time.
+#[derive(Debug, PartialEq)]
+enum Idle {
+    /// Another watcher was started; this one exits.
+    Superseded,
+    /// Requests were made recently; check again after given time.
+    Wait(Duration),
+    /// No requests for idle sleep time.
+    Expired(Duration),
+}
+
+impl Standby {
+    /// Hidden command line option running the idle watcher for a server.
+    const WATCH_OPTION: &'static str = "--idle-watch";
+    const STATE_DIR: &'static str = "standby";
+    const EXTENSION: &'static str = "json";
+
+    /// Standby for the server hosting named backend, none if backend has no server configured.
+    pub fn new(backend: Option<&str>) -> Result<Option<Self>> {
+        trace!("Standby::new(backend: Option<&str>) -> Result<Option<Self>>");
+        let config = config::get_config();
+        let Some(name) = config.backend(backend)?.server else {
+            return Ok(None);
+        };
+        let server = config.server(Some(&name))?;
+        Ok(Some(Self { server }))
+    }
+
+    /// Hold request until the model is loaded, starting server if needed, then record the
+    /// request for idle timer.
+    pub async fn ready(&self) -> Result<()> {
+        trace!("Standby::ready(&self) -> Result<()>");
+        let server = &self.server;
+        let lifecycle = Lifecycle::new(server)?;
+        let state = lifecycle.probe().await;
+        if state != State::ModelLoaded {
+            eprintln!(
+                "{} server is {state}, prompt queued until it is ready",
+                server.name
+            );
+            lifecycle
+                .start(|state, elapsed| {
+                    eprintln!("[{:>3}s] {} {state}", elapsed.as_secs(), server.name)
+                })
+                .await?;
+        }
+        self.touch()
+    }
+
+    /// Update last request time and ensure a watcher is running, if idle sleep is enabled.
+    fn touch(&self) -> Result<()> {
+        trace!("Standby::touch(&self) -> Result<()>");
+        if self.server.idle_sleep.is_none() {
+            return Ok(());
+        }
+        let path = state_path(&self.server.name)?;
+        let state = IdleState::update(&path, |state| state.last_request = now())?;
+        if !state.watcher.is_some_and(alive) {
+            self.spawn_watcher()?;
+        }
+        Ok(())
+    }
+
+    /// Start detached watcher process; it records itself in state file on start.
+    fn spawn_watcher(&self) -> Result<()> {
+        trace!("Standby::spawn_watcher(&self) -> Result<()>");
+        let mut command = process::Command::new(env::current_exe()?);
+        command
+            .arg("--config-file")
+            .arg(config::get_config_path())
+            .arg(Self::WATCH_OPTION)
+            .arg(&self.server.name)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // own process group so that terminal signals, e.g. Ctrl-C, do not reach the watcher
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let child = command.spawn()?;
+        debug!(
+            "idle watcher for {} started: {}",
+            self.server.name,
+            child.id()
+        );
+        Ok(())
+    }
+
+    /// Idle watcher loop: sleep until idle time elapsed since last request, then shut server
+    /// down. Exits without action if superseded by a watcher started later.
+    pub async fn watch(name: &str) -> Result<()> {
+        trace!("Standby::watch(name: &str) -> Result<()>");
+        let server = config::get_config().server(Some(name))?;
+        let idle_sleep = server.idle_sleep.ok_or_else(|| {
+            AppError::InvalidConfig(format!("server {name}: idle_sleep not configured"))
+        })?;
+        let path = state_path(name)?;
+        let lifecycle = Lifecycle::new(&server)?;
+        let watcher = process::id();
+        IdleState::update(&path, |state| state.watcher = Some(watcher))?;
+
+        loop {
+            let idle = match IdleState::load(&path)?.idle(watcher, now(), idle_sleep) {
+                Idle::Superseded => {
+                    debug!("idle watcher for {name} superseded");
+                    return Ok(());
+                }
+                Idle::Wait(remaining) => {
+                    tokio::time::sleep(remaining).await;
+                    continue;
+                }
+                Idle::Expired(idle) => idle,
+            };
+
+            if lifecycle.probe().await != State::Sleeping {
+                // requests from other clients keep the server busy; wait another period
+                if lifecycle.in_flight().await.is_some_and(|count| count > 0) {
+                    info!("{name} idle but busy, shutdown postponed");
+                    IdleState::update(&path, |state| state.last_request = now())?;
+                    continue;
+                }
+                info!("{name} idle for {}s, shutting down", idle.as_secs());
+                if let Err(e) = lifecycle.stop(|_, _| {}).await {
+                    warn!("idle shutdown of {name} failed: {e}");
+                }
+            }
+            // a request may have started a newer watcher meanwhile; leave its record alone
+            IdleState::update(&path, |state| {
+                if state.watcher == Some(watcher) {
+                    state.watcher = None;
+                }
+            })?;
+            return Ok(());
+        }
+    }
+}
+
+impl IdleState {
+    /// Current state, read under shared lock; missing or invalid state file reads as default.
+    fn load(path: &Path) -> Result<Self> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        file.lock_shared()?;
+        Ok(Self::read(&mut file))
+    }
+
+    /// Read, modify and write state under exclusive lock, so that concurrent CLI processes and
+    /// the watcher do not overwrite each other changes. Returns the updated state.
+    fn update(path: &Path, modify: impl FnOnce(&mut Self)) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.lock()?;
+        let mut state = Self::read(&mut file);
+        modify(&mut state);
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(serde_json::to_string(&state)?.as_bytes())?;
+        Ok(state)
+    }
+
+    fn read(file: &mut File) -> Self {
+        let mut json = String::new();
+        match file.read_to_string(&mut json) {
+            Ok(_) => serde_json::from_str(&json).unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Watcher step at given time, in seconds since epoch.
+    fn idle(&self, watcher: u32, now: u64, idle_sleep: Duration) -> Idle {
+        if self.watcher != Some(watcher) {
+            return Idle::Superseded;
+        }
+        let idle = Duration::from_secs(now.saturating_sub(self.last_request));
+        match idle < idle_sleep {
+            true => Idle::Wait(idle_sleep - idle),
+            false => Idle::Expired(idle),
+        }
+    }
+}
+
+fn state_path(name: &str) -> Result<PathBuf> {
+    dirs::data_file(Standby::STATE_DIR, name, Standby::EXTENSION)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Whether process is running; on systems without procfs a new watcher is always spawned and
+/// the previous one exits when it finds itself superseded.
+fn alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, thread, time::Duration};
+
+    use crate::standby::{Idle, IdleState};
+
+    #[test]
+    fn idle() {
+        let sleep = Duration::from_secs(600);
+        let state = IdleState {
+            last_request: 1000,
+            watcher: Some(7),
+        };
+        assert_eq!(state.idle(8, 1000, sleep), Idle::Superseded);
+        assert_eq!(state.idle(7, 1000, sleep), Idle::Wait(sleep));
+        assert_eq!(
+            state.idle(7, 1599, sleep),
+            Idle::Wait(Duration::from_secs(1))
+        );
+        assert_eq!(state.idle(7, 1600, sleep), Idle::Expired(sleep));
+        // clock moved backwards
+        assert_eq!(state.idle(7, 900, sleep), Idle::Wait(sleep));
+    }
+
+    #[test]
+    fn update() {
+        let path = env::temp_dir().join(format!("jarvis-standby-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(IdleState::load(&path).unwrap(), IdleState::default());
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for _ in 0..50 {
+                        IdleState::update(&path, |state| state.last_request += 1).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let state = IdleState::update(&path, |state| state.watcher = Some(7)).unwrap();
+        assert_eq!(
+            state,
+            IdleState {
+                last_request: 200,
+                watcher: Some(7)
+            }
+        );
+        assert_eq!(IdleState::load(&path).unwrap(), state);
+        fs::remove_file(&path).unwrap();
+    }
+}
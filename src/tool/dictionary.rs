@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use log::trace;
+use serde_json::{Value, json};
+
+use crate::{
+    command::dictionary::DictionaryCommand,
+    error::Result,
+    slm::ToolDefinition,
+    tool::{Tool, argument},
+};
+
+/// Dictionary lookup, see [DictionaryCommand].
+pub struct DefineTool;
+
+#[async_trait(?Send)]
+impl Tool for DefineTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "define".to_string(),
+            description: "Look up the definitions of a word in the dictionary".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "word": {"type": "string", "description": "Word to define"}
+                },
+                "required": ["word"]
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String> {
+        trace!("DefineTool::call(&self, arguments: &Value) -> Result<String>");
+        let word = argument(arguments, "word")?;
+        // no generated fallback here, the calling model answers from its own knowledge
+        let command = DictionaryCommand::new(word);
+        let lookup = command.lookup().await?;
+        if lookup.definitions.is_empty() && lookup.suggestions.is_empty() {
+            return Ok(format!("No definition found for {word}"));
+        }
+        command.render(&lookup)
+    }
+}
@@ -0,0 +1,202 @@
+pub mod dictionary;
+pub mod server;
+
+use async_trait::async_trait;
+use log::{debug, trace, warn};
+use serde_json::Value;
+
+use crate::{
+    error::{AppError, Result},
+    slm::{Message, Role, SlmBackend, SlmRequest, ToolCall, ToolDefinition},
+    tool::{dictionary::DefineTool, server::ServerStatusTool},
+    util::console,
+};
+
+/// Action the SLM may request while answering, declared by a JSON schema of its arguments.
+#[async_trait(?Send)]
+pub trait Tool {
+    fn definition(&self) -> ToolDefinition;
+
+    /// Whether calling the tool changes state outside the conversation; such calls are executed
+    /// only after user confirmation.
+    fn side_effects(&self) -> bool {
+        false
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String>;
+}
+
+/// Tools advertised to backends supporting function calling, and the loop executing calls
+/// requested by the model until it gives the final answer.
+pub struct ToolRegistry {
+    tools: Vec<Box<dyn Tool>>,
+    confirm: fn(&str) -> Result<bool>,
+}
+
+impl ToolRegistry {
+    /// Default upper limit of request rounds, used when not configured.
+    pub const MAX_ITERATIONS: usize = 5;
+    const DECLINED: &'static str = "User declined to run the tool.";
+
+    /// Registry with built-in tools.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            tools: Vec::new(),
+            confirm: console::confirm,
+        };
+        registry.register(Box::new(DefineTool));
+        registry.register(Box::new(ServerStatusTool));
+        registry
+    }
+
+    pub fn register(&mut self, tool: Box<dyn Tool>) {
+        self.tools.push(tool);
+    }
+
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools.iter().map(|tool| tool.definition()).collect()
+    }
+
+    /// Send request advertising tools and execute requested calls, feeding results back, until
+    /// the model answers without calls or given number of rounds is exceeded.
+    pub async fn run(
+        &self,
+        backend: &dyn SlmBackend,
+        mut request: SlmRequest,
+        max_iterations: usize,
+    ) -> Result<String> {
+        trace!(
+            "ToolRegistry::run(&self, backend: &dyn SlmBackend, mut request: SlmRequest, max_iterations: usize) -> Result<String>"
+        );
+        let definitions = self.definitions();
+        for _ in 0..max_iterations {
+            let reply = backend.chat(&request, &definitions).await?;
+            if reply.tool_calls.is_empty() {
+                return Ok(reply.content);
+            }
+
+            let mut message = Message::new(Role::Assistant, &reply.content);
+            message.tool_calls = reply.tool_calls.clone();
+            request.push_turn(message);
+            for call in &reply.tool_calls {
+                let result = match self.call(call).await {
+                    Ok(result) => result,
+                    Err(e) => {
+                        warn!("tool {} failed: {e}", call.name);
+                        format!("Error: {e}")
+                    }
+                };
+                request.push_turn(Message::tool_result(call, &result));
+            }
+        }
+        Err(AppError::Tool(format!(
+            "no final answer after {max_iterations} iterations"
+        )))
+    }
+
+    async fn call(&self, call: &ToolCall) -> Result<String> {
+        debug!("tool call {}({})", call.name, call.arguments);
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.definition().name == call.name)
+            .ok_or_else(|| AppError::Tool(format!("unknown tool {}", call.name)))?;
+        if tool.side_effects()
+            && !(self.confirm)(&format!("Run {}({})?", call.name, call.arguments))?
+        {
+            return Ok(Self::DECLINED.to_string());
+        }
+        tool.call(&call.arguments).await
+    }
+}
+
+/// String argument of a tool call, failing if missing.
+fn argument<'a>(arguments: &'a Value, name: &str) -> Result<&'a str> {
+    arguments[name]
+        .as_str()
+        .ok_or_else(|| AppError::Tool(format!("missing argument {name}")))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc};
+
+    use async_trait::async_trait;
+    use serde_json::{Value, json};
+
+    use crate::{
+        error::Result,
+        slm::{Reply, SlmRequest, ToolCall, ToolDefinition, fake::FakeBackend},
+        tool::{Tool, ToolRegistry},
+    };
+
+    struct Shutdown {
+        calls: Rc<Cell<usize>>,
+    }
+
+    #[async_trait(?Send)]
+    impl Tool for Shutdown {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "shutdown".to_string(),
+                description: "Shutdown server".to_string(),
+                parameters: json!({"type": "object", "properties": {}}),
+            }
+        }
+
+        fn side_effects(&self) -> bool {
+            true
+        }
+
+        async fn call(&self, _arguments: &Value) -> Result<String> {
+            self.calls.set(self.calls.get() + 1);
+            Ok("done".to_string())
+        }
+    }
+
+    fn reply(content: &str, calls: &[&str]) -> Reply {
+        Reply {
+            content: content.to_string(),
+            tool_calls: (0..)
+                .zip(calls)
+                .map(|(index, name)| ToolCall {
+                    id: format!("call_{index}"),
+                    name: name.to_string(),
+                    arguments: json!({}),
+                })
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn run() {
+        let calls = Rc::new(Cell::new(0));
+        let mut registry = ToolRegistry {
+            tools: Vec::new(),
+            confirm: |_| Ok(false),
+        };
+        registry.register(Box::new(Shutdown {
+            calls: calls.clone(),
+        }));
+
+        let backend = FakeBackend::with_replies(vec![
+            reply("", &["shutdown", "missing"]),
+            reply("Server was not stopped", &[]),
+        ]);
+        let answer = registry
+            .run(&backend, SlmRequest::new("stop server"), 3)
+            .await
+            .unwrap();
+        assert_eq!(answer, "Server was not stopped");
+        assert_eq!(calls.get(), 0);
+        let turns = backend.turns();
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[1].len(), 3);
+        assert_eq!(turns[1][1].content, ToolRegistry::DECLINED);
+        assert!(turns[1][2].content.starts_with("Error:"));
+
+        let backend = FakeBackend::with_replies(vec![reply("", &["missing"]); 3]);
+        let result = registry.run(&backend, SlmRequest::new("loop"), 2).await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use log::trace;
+use serde_json::{Value, json};
+
+use crate::{command::server::ServerCommand, error::Result, slm::ToolDefinition, tool::Tool};
+
+/// Reachability of the SLM server and service, see [ServerCommand].
+pub struct ServerStatusTool;
+
+#[async_trait(?Send)]
+impl Tool for ServerStatusTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "server_status".to_string(),
+            description: "Check whether the SLM server machine and service are up".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "server": {
+                        "type": "string",
+                        "description": "Server name, default server if missing"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: &Value) -> Result<String> {
+        trace!("ServerStatusTool::call(&self, arguments: &Value) -> Result<String>");
+        let server = arguments["server"].as_str();
+        let status = ServerCommand::new("server", Some("status"), server)
+            .exec()
+            .await?;
+        Ok(status.unwrap_or_default())
+    }
+}
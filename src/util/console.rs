@@ -0,0 +1,31 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::error::Result;
+
+/// Ask user a yes/no question on the terminal, defaulting to no. Not interactive standard
+/// input, e.g. piped data, is never taken as consent.
+pub fn confirm(question: &str) -> Result<bool> {
+    if !interactive() {
+        return Ok(false);
+    }
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Whether standard input is an interactive terminal, so that user can be asked.
+pub fn interactive() -> bool {
+    io::stdin().is_terminal()
+}
+
+/// Ask user for a line of text on the terminal; returns trimmed answer, empty if user just
+/// pressed enter.
+pub fn ask(question: &str) -> Result<String> {
+    print!("{question}: ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
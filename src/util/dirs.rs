@@ -0,0 +1,38 @@
+use std::{env, fs, path::PathBuf};
+
+use log::trace;
+
+use crate::error::{AppError, Result};
+
+const APP_NAME: &str = "jarvis";
+
+/// Application data directory, `$XDG_DATA_HOME/jarvis` or `~/.local/share/jarvis` if XDG
+/// variable is not set; sub-directory is created if missing.
+pub fn data_dir(sub_dir: &str) -> Result<PathBuf> {
+    trace!("dirs::data_dir(sub_dir: &str) -> Result<PathBuf>");
+    let base_dir = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".local").join("share"),
+            None => return Err(AppError::Fatal("data directory: HOME not set".to_string())),
+        },
+    };
+    let dir = base_dir.join(APP_NAME).join(sub_dir);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Path of a named data file, e.g. a session or an index; name is restricted to letters,
+/// digits, dash, underscore and dot so that it cannot escape the data directory.
+pub fn data_file(sub_dir: &str, name: &str, extension: &str) -> Result<PathBuf> {
+    trace!("dirs::data_file(sub_dir: &str, name: &str, extension: &str) -> Result<PathBuf>");
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !valid {
+        return Err(AppError::Fatal(format!("invalid data file name {name}")));
+    }
+    Ok(data_dir(sub_dir)?.join(format!("{name}.{extension}")))
+}
@@ -1 +1,3 @@
+pub mod console;
+pub mod dirs;
 pub mod net;
@@ -1,45 +1,350 @@
-use crate::error::Result;
-use log::trace;
+use crate::error::{AppError, Result};
+use futures::future;
+use if_addrs::IfAddr;
+use log::{debug, trace, warn};
 use std::{
-    net::{Shutdown, SocketAddr, TcpStream, UdpSocket},
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
     str::FromStr,
-    time::Duration,
+    time::{Duration, Instant},
 };
+use tokio::{
+    net::{TcpStream, UdpSocket, lookup_host},
+    time::timeout,
+};
+
+/// Result of a TCP connect probe to a host and port.
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub host: String,
+    pub port: u16,
+    /// Address connected to, or the last one tried; none if host was not resolved.
+    pub address: Option<SocketAddr>,
+    pub outcome: Outcome,
+    /// Time spent, including name resolution.
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Open,
+    /// Host answered but nothing listens on port, so the host itself is up.
+    Refused,
+    Timeout,
+    Unresolved(String),
+    Failed(String),
+}
+
+impl Probe {
+    pub fn is_open(&self) -> bool {
+        self.outcome == Outcome::Open
+    }
+
+    /// Connect latency, only for open ports.
+    pub fn latency(&self) -> Option<Duration> {
+        self.is_open().then_some(self.elapsed)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Open => write!(f, "open"),
+            Outcome::Refused => write!(f, "connection refused"),
+            Outcome::Timeout => write!(f, "timed out"),
+            Outcome::Unresolved(e) => write!(f, "not resolved: {e}"),
+            Outcome::Failed(e) => write!(f, "{e}"),
+        }
+    }
+}
 
-pub fn connect_timeout(ip: &str, port: u16, timeout: u16) -> bool {
-    trace!("net:try_connect(ip: &str, port: u16, timeout: u16) -> bool");
-    if let Ok(addr) = SocketAddr::from_str(&format!("{ip}:{port}")) {
-        if let Ok(socket) = TcpStream::connect_timeout(&addr, Duration::from_millis(timeout as u64))
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} {}", self.host, self.port, self.outcome)?;
+        if let Some(address) = self.address
+            && address.ip().to_string() != self.host
         {
-            if let Ok(_) = socket.shutdown(Shutdown::Write) {
-                return true;
+            write!(f, " via {address}")?;
+        }
+        match self.latency() {
+            Some(latency) => write!(f, " in {} ms", latency.as_millis()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Socket addresses for host name or IP literal; IPv6 may be enclosed in brackets.
+pub async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+    trace!("net::resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>>");
+    let host = host.trim();
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    if let Ok(ip) = IpAddr::from_str(host) {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    let addresses: Vec<SocketAddr> = lookup_host((host, port)).await?.collect();
+    if addresses.is_empty() {
+        return Err(AppError::InvalidAddress(format!(
+            "{host}: no address found"
+        )));
+    }
+    Ok(addresses)
+}
+
+/// Connect to host port within deadline, covering name resolution and trying resolved
+/// addresses in turn until one accepts.
+pub async fn probe(host: &str, port: u16, deadline: Duration) -> Probe {
+    trace!("net::probe(host: &str, port: u16, deadline: Duration) -> Probe");
+    let started = Instant::now();
+    let mut address = None;
+    let connect = async {
+        let addresses = match resolve(host, port).await {
+            Ok(addresses) => addresses,
+            Err(e) => return Outcome::Unresolved(e.to_string()),
+        };
+        let mut outcome = Outcome::Timeout;
+        for candidate in addresses {
+            address = Some(candidate);
+            outcome = match TcpStream::connect(candidate).await {
+                Ok(_) => return Outcome::Open,
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => Outcome::Refused,
+                Err(e) => Outcome::Failed(e.to_string()),
+            };
+        }
+        outcome
+    };
+    let outcome = timeout(deadline, connect).await.unwrap_or(Outcome::Timeout);
+    let probe = Probe {
+        host: host.to_string(),
+        port,
+        address,
+        outcome,
+        elapsed: started.elapsed(),
+    };
+    debug!("probe {probe}");
+    probe
+}
+
+/// Probe all targets concurrently, each with its own deadline; results in targets order.
+pub async fn probe_all(targets: &[(&str, u16, Duration)]) -> Vec<Probe> {
+    trace!("net::probe_all(targets: &[(&str, u16, Duration)]) -> Vec<Probe>");
+    let probes = targets
+        .iter()
+        .map(|(host, port, deadline)| probe(host, *port, *deadline));
+    future::join_all(probes).await
+}
+
+/// Six bytes hardware address written as hex pairs separated by `:` or `-`, or as twelve hex
+/// digits; SecureOn passwords use the same notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacAddress([u8; 6]);
+
+impl FromStr for MacAddress {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = |reason: &str| AppError::InvalidAddress(format!("{value}: {reason}"));
+        let value = value.trim();
+        let pairs: Vec<&str> = match value.contains([':', '-']) {
+            true => value.split([':', '-']).collect(),
+            false => (0..value.len())
+                .step_by(2)
+                .filter_map(|i| value.get(i..(i + 2).min(value.len())))
+                .collect(),
+        };
+        if pairs.len() != 6 {
+            return Err(invalid("expected 6 bytes"));
+        }
+        let mut bytes = [0u8; 6];
+        for (byte, pair) in bytes.iter_mut().zip(pairs) {
+            if pair.len() != 2 || !pair.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid(&format!("invalid byte '{pair}'")));
             }
+            *byte = u8::from_str_radix(pair, 16)?;
         }
+        Ok(Self(bytes))
     }
-    return false;
 }
 
-pub fn send_wol(mac_address: &str) -> Result<()> {
-    trace!("net::send_wol(mac_address: &str) -> Result<()>");
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs: Vec<String> = self.0.iter().map(|byte| format!("{byte:02x}")).collect();
+        write!(f, "{}", pairs.join(":"))
+    }
+}
+
+/// Where Wake-on-LAN packets are broadcast.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Broadcast {
+    /// Explicit address, e.g. directed broadcast of a routed subnet.
+    Address(Ipv4Addr),
+    /// Directed broadcast of the named local interface.
+    Interface(String),
+    /// Limited broadcast plus directed broadcast of every local IPv4 interface, so that the
+    /// packet reaches the target whichever interface it is attached to.
+    Local,
+}
 
-    let mac_address = mac_address.replace(['-', ':'], "");
-    let mut mac = [0u8; 6];
-    for i in 0..6 {
-        let byte_str = &mac_address[i * 2..i * 2 + 2];
-        mac[i] = u8::from_str_radix(byte_str, 16)?;
+/// Wake-on-LAN magic packet and its delivery: target ports and number of rounds, since UDP
+/// gives no guarantee a single packet arrives.
+#[derive(Debug, Clone)]
+pub struct WakeOnLan {
+    pub mac: MacAddress,
+    /// SecureOn password appended to the packet, for NICs configured to require it.
+    pub password: Option<MacAddress>,
+    pub broadcast: Broadcast,
+    pub ports: Vec<u16>,
+    pub repeat: u32,
+}
+
+impl WakeOnLan {
+    pub const PORTS: [u16; 2] = [9, 7];
+    pub const REPEAT: u32 = 3;
+    const REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+    pub fn new(mac: MacAddress) -> Self {
+        Self {
+            mac,
+            password: None,
+            broadcast: Broadcast::Local,
+            ports: Self::PORTS.to_vec(),
+            repeat: Self::REPEAT,
+        }
     }
 
-    let mut packet = Vec::new();
-    for _ in 0..6 {
-        packet.push(0xFF); // 6 bytes of 0xFF
+    /// Six bytes of 0xFF, sixteen repetitions of MAC address and optional SecureOn password.
+    pub fn packet(&self) -> Vec<u8> {
+        let mut packet = vec![0xFF; 6];
+        for _ in 0..16 {
+            packet.extend_from_slice(&self.mac.0);
+        }
+        if let Some(password) = &self.password {
+            packet.extend_from_slice(&password.0);
+        }
+        packet
     }
-    for _ in 0..16 {
-        packet.extend_from_slice(&mac); // 16 repetitions of MAC address
+
+    /// Broadcast addresses resolved against local interfaces.
+    pub fn addresses(&self) -> Result<Vec<Ipv4Addr>> {
+        trace!("WakeOnLan::addresses(&self) -> Result<Vec<Ipv4Addr>>");
+        let interfaces = || -> Result<Vec<(String, Ipv4Addr)>> {
+            let interfaces = if_addrs::get_if_addrs()?
+                .into_iter()
+                .filter(|interface| !interface.is_loopback())
+                .filter_map(|interface| match interface.addr {
+                    IfAddr::V4(addr) => addr.broadcast.map(|broadcast| (interface.name, broadcast)),
+                    IfAddr::V6(_) => None,
+                })
+                .collect();
+            Ok(interfaces)
+        };
+        match &self.broadcast {
+            Broadcast::Address(address) => Ok(vec![*address]),
+            Broadcast::Interface(name) => interfaces()?
+                .into_iter()
+                .find(|(interface, _)| interface == name)
+                .map(|(_, broadcast)| vec![broadcast])
+                .ok_or_else(|| {
+                    AppError::InvalidAddress(format!("no IPv4 broadcast on interface {name}"))
+                }),
+            Broadcast::Local => {
+                let mut addresses = vec![Ipv4Addr::BROADCAST];
+                for (_, broadcast) in interfaces()? {
+                    if !addresses.contains(&broadcast) {
+                        addresses.push(broadcast);
+                    }
+                }
+                Ok(addresses)
+            }
+        }
     }
 
-    let socket = UdpSocket::bind("0.0.0.0:0")?;
-    socket.set_broadcast(true)?;
-    socket.send_to(&packet, "255.255.255.255:9")?;
+    /// Send the packet to every broadcast address and port, repeated; fails only if no
+    /// destination could be reached at all. Returns destinations the packet was sent to.
+    pub async fn send(&self) -> Result<Vec<SocketAddrV4>> {
+        trace!("WakeOnLan::send(&self) -> Result<Vec<SocketAddrV4>>");
+        let packet = self.packet();
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.set_broadcast(true)?;
 
-    Ok(())
+        let mut destinations = Vec::new();
+        for address in self.addresses()? {
+            for port in &self.ports {
+                destinations.push(SocketAddrV4::new(address, *port));
+            }
+        }
+        let mut sent = Vec::new();
+        let mut last_error = None;
+        for round in 0..self.repeat.max(1) {
+            if round > 0 {
+                tokio::time::sleep(Self::REPEAT_INTERVAL).await;
+            }
+            for destination in &destinations {
+                match socket.send_to(&packet, destination).await {
+                    Ok(_) if !sent.contains(destination) => sent.push(*destination),
+                    Ok(_) => {}
+                    Err(e) => {
+                        debug!("Wake-on-LAN to {destination}: {e}");
+                        last_error = Some(e);
+                    }
+                }
+            }
+        }
+        if sent.is_empty() {
+            return Err(match last_error {
+                Some(e) => e.into(),
+                None => AppError::InvalidAddress("no broadcast address".to_string()),
+            });
+        }
+        if let Some(e) = last_error {
+            warn!("Wake-on-LAN not sent to all destinations: {e}");
+        }
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+
+    use crate::util::net::{self, MacAddress, Outcome, WakeOnLan};
+
+    #[tokio::test]
+    async fn probe() {
+        let addresses = net::resolve("[::1]", 22).await.unwrap();
+        assert_eq!(addresses[0].to_string(), "[::1]:22");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let deadline = Duration::from_secs(1);
+        let probes =
+            net::probe_all(&[("localhost", port, deadline), ("127.0.0.1", port, deadline)]).await;
+        assert!(probes.iter().all(|probe| probe.latency().is_some()));
+        drop(listener);
+        let probe = net::probe("127.0.0.1", port, deadline).await;
+        assert_eq!(probe.outcome, Outcome::Refused);
+    }
+
+    #[test]
+    fn mac_address() {
+        let mac: MacAddress = "10-7C-61-5f-10-be".parse().unwrap();
+        assert_eq!(mac.to_string(), "10:7c:61:5f:10:be");
+        assert_eq!("107c615f10be".parse::<MacAddress>().unwrap(), mac);
+        assert!("10:7c:61:5f:10".parse::<MacAddress>().is_err());
+        assert!("10:7c:61:5f:10:b".parse::<MacAddress>().is_err());
+        assert!("10:7c:61:5f:10:zz".parse::<MacAddress>().is_err());
+        assert!("107c615f10bé".parse::<MacAddress>().is_err());
+
+        let mut wol = WakeOnLan::new(mac);
+        assert_eq!(wol.packet().len(), 102);
+        wol.password = Some("01:02:03:04:05:06".parse().unwrap());
+        let packet = wol.packet();
+        assert_eq!(packet.len(), 108);
+        assert_eq!(&packet[6..12], &[0x10, 0x7c, 0x61, 0x5f, 0x10, 0xbe]);
+        assert_eq!(&packet[102..], &[1, 2, 3, 4, 5, 6]);
+    }
 }